@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use color_eyre::{eyre::eyre, Result, Section};
+
+use crate::config::{expand_home, Git, Repository};
+
+/// Commit activity for a single project on a single day
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayActivity {
+    pub date: NaiveDate,
+    pub project_id: String,
+    pub first: NaiveTime,
+    pub last: NaiveTime,
+    pub commits: usize,
+}
+
+impl DayActivity {
+    /// Estimated hours based on the window between the first and last commit,
+    /// rounded up to the nearest half hour with a minimum of half an hour
+    pub fn estimate(&self) -> f32 {
+        let minutes = (self.last - self.first).num_minutes().max(0) as f32;
+        ((minutes / 30.0).ceil() / 2.0).max(0.5)
+    }
+
+    /// Human readable activity window, e.g. `09:12-16:40 ~7.5h`
+    pub fn window(&self) -> String {
+        format!(
+            "{}-{} ~{:.1}h",
+            self.first.format("%H:%M"),
+            self.last.format("%H:%M"),
+            self.estimate()
+        )
+    }
+}
+
+/// Collects commit activity between `from` and `to` (inclusive) for every configured repository.
+/// Repositories mapped to the same project are merged into a single window per day.
+pub fn collect(git: &Git, from: NaiveDate, to: NaiveDate) -> Result<Vec<DayActivity>> {
+    let mut merged: BTreeMap<(NaiveDate, String), DayActivity> = BTreeMap::new();
+
+    for repository in &git.repositories {
+        let output = git_log(repository, git.author.as_deref(), from, to)?;
+        for activity in parse_log(&output, &repository.project)? {
+            merged
+                .entry((activity.date, activity.project_id.clone()))
+                .and_modify(|a| {
+                    a.first = a.first.min(activity.first);
+                    a.last = a.last.max(activity.last);
+                    a.commits += activity.commits;
+                })
+                .or_insert(activity);
+        }
+    }
+
+    Ok(merged.into_values().collect())
+}
+
+fn git_log(
+    repository: &Repository,
+    author: Option<&str>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<String> {
+    let path = expand_home(&repository.path);
+    let author = match author {
+        Some(a) => a.to_string(),
+        None => git(&path, &["config", "user.email"])?.trim().to_string(),
+    };
+
+    git(
+        &path,
+        &[
+            "log",
+            "--all",
+            "--no-merges",
+            &format!("--author={author}"),
+            &format!("--since={} 00:00:00", from.format("%Y-%m-%d")),
+            &format!("--until={} 23:59:59", to.format("%Y-%m-%d")),
+            "--format=%aI",
+        ],
+    )
+}
+
+fn git(path: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .suggestion("Check that the repositories in the [git] section of config.toml exist"));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Groups author timestamps (`git log --format=%aI`) into one activity window per day.
+/// Days are determined by the author's own time zone.
+fn parse_log(output: &str, project_id: &str) -> Result<Vec<DayActivity>> {
+    let mut days: BTreeMap<NaiveDate, DayActivity> = BTreeMap::new();
+
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let timestamp = DateTime::parse_from_rfc3339(line.trim())?.naive_local();
+        let (date, time) = (timestamp.date(), timestamp.time());
+        days.entry(date)
+            .and_modify(|a| {
+                a.first = a.first.min(time);
+                a.last = a.last.max(time);
+                a.commits += 1;
+            })
+            .or_insert(DayActivity {
+                date,
+                project_id: project_id.to_string(),
+                first: time,
+                last: time,
+                commits: 1,
+            });
+    }
+
+    Ok(days.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+
+    use crate::activity::parse_log;
+
+    #[test]
+    fn test_parse_log() {
+        let output = "2024-04-23T16:40:12+02:00\n\
+                      2024-04-23T09:12:00+02:00\n\
+                      2024-04-23T12:00:00+02:00\n\
+                      2024-04-24T10:00:00+02:00\n";
+        let days = parse_log(output, "project").unwrap();
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2024, 4, 23).unwrap());
+        assert_eq!(days[0].first, NaiveTime::from_hms_opt(9, 12, 0).unwrap());
+        assert_eq!(days[0].last, NaiveTime::from_hms_opt(16, 40, 12).unwrap());
+        assert_eq!(days[0].commits, 3);
+        assert_eq!(days[0].estimate(), 7.5);
+        assert_eq!(days[1].commits, 1);
+        assert_eq!(
+            days[1].estimate(),
+            0.5,
+            "single commits count as half an hour"
+        );
+    }
+
+    #[test]
+    fn test_parse_log_invalid() {
+        assert!(parse_log("not a date", "project").is_err());
+        assert!(parse_log("", "project").unwrap().is_empty());
+    }
+}
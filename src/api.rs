@@ -1,11 +1,163 @@
-use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
 
-use chrono::{DateTime, Datelike};
 use color_eyre::eyre::eyre;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::config::Config;
-use crate::model::Message;
+
+/// Seconds before a request is abandoned
+const TIMEOUT: u64 = 5;
+/// Number of characters of a response body kept in errors
+const EXCERPT_LENGTH: usize = 120;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ApiError {
+    #[error("authentication failed with status {status}")]
+    Auth { status: i32 },
+    #[error("not found: {url}")]
+    NotFound { url: String },
+    #[error("server error {status}: {excerpt}")]
+    Server { status: i32, excerpt: String },
+    #[error("unexpected status {status}: {excerpt}")]
+    Status { status: i32, excerpt: String },
+    #[error("request timed out: {url}")]
+    Timeout { url: String },
+    #[error("request failed: {reason}")]
+    Connection { reason: String },
+    #[error("could not decode response: {reason} (body: {excerpt})")]
+    Decode { reason: String, excerpt: String },
+}
+
+impl ApiError {
+    /// Failures which may succeed when the request is repeated
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ApiError::Server { .. } | ApiError::Timeout { .. } | ApiError::Connection { .. }
+        )
+    }
+
+    /// A message explaining the failure to the user, and what to do about it
+    pub fn user_message(&self) -> String {
+        match self {
+            ApiError::Auth { .. } => "API key rejected — run `timet-tui api-key`".to_string(),
+            ApiError::NotFound { .. } => {
+                "Endpoint not found — check `api.endpoint` in config.toml".to_string()
+            }
+            ApiError::Server { status, .. } => {
+                format!("Timet failed with status {status} — try again later")
+            }
+            ApiError::Status { status, excerpt } => {
+                format!("Request rejected with status {status}: {excerpt}")
+            }
+            ApiError::Timeout { .. } => {
+                "Timet did not respond in time — check your connection".to_string()
+            }
+            ApiError::Connection { .. } => {
+                "Could not reach Timet — check your connection and `api.endpoint`".to_string()
+            }
+            ApiError::Decode { excerpt, .. } => {
+                format!("Unexpected response from Timet: {excerpt}")
+            }
+        }
+    }
+
+    fn from_minreq(url: &str, err: minreq::Error) -> Self {
+        match err {
+            minreq::Error::IoError(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                ) =>
+            {
+                ApiError::Timeout {
+                    url: url.to_string(),
+                }
+            }
+            e => ApiError::Connection {
+                reason: e.to_string(),
+            },
+        }
+    }
+}
+
+/// The user message of an API error, or the root cause of any other error
+pub fn describe(err: &color_eyre::Report) -> String {
+    match err.downcast_ref::<ApiError>() {
+        Some(api_error) => api_error.user_message(),
+        None => err.root_cause().to_string(),
+    }
+}
+
+fn excerpt(body: &str) -> String {
+    body.chars().take(EXCERPT_LENGTH).collect()
+}
+
+/// Classifies the status code of a response
+fn check_status(url: &str, status: i32, body: &str) -> Result<(), ApiError> {
+    match status {
+        200..=299 => Ok(()),
+        401 | 403 => Err(ApiError::Auth { status }),
+        404 => Err(ApiError::NotFound {
+            url: url.to_string(),
+        }),
+        429 | 500..=599 => Err(ApiError::Server {
+            status,
+            excerpt: excerpt(body),
+        }),
+        _ => Err(ApiError::Status {
+            status,
+            excerpt: excerpt(body),
+        }),
+    }
+}
+
+fn decode<T: DeserializeOwned>(body: &str) -> Result<T, ApiError> {
+    serde_json::from_str(body).map_err(|err| ApiError::Decode {
+        reason: err.to_string(),
+        excerpt: excerpt(body),
+    })
+}
+
+/// How often and how patiently transient failures are retried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Retry {
+    pub attempts: u32,
+    /// Delay before the first retry, doubled for every following retry
+    pub delay: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            attempts: 3,
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl Retry {
+    /// Runs the request, repeating it with exponential backoff while it fails transiently.
+    /// Sleeps between attempts, so requests are only made from background tasks.
+    pub fn run<T>(&self, mut request: impl FnMut() -> Result<T, ApiError>) -> Result<T, ApiError> {
+        let mut delay = self.delay;
+        let mut attempt = 1;
+        loop {
+            match request() {
+                Err(err) if err.is_transient() && attempt < self.attempts => {
+                    log::warn!("attempt {attempt} failed, retrying in {delay:?}: {err}");
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct TimetEntries {
@@ -26,6 +178,20 @@ pub struct TimetEntry {
     pub project_id: String,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimetProjects {
+    pub projects: Option<Vec<TimetProject>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TimetProject {
+    pub project_id: String,
+    pub project_name: String,
+    #[serde(default)]
+    pub archived: bool,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Hours<'a> {
@@ -34,10 +200,24 @@ pub struct Hours<'a> {
     pub hours: f32,
 }
 
+/// Status code and body of the response to posting hours, kept for the submission log
+#[derive(Debug, Clone)]
+pub struct Receipt {
+    pub status_code: i32,
+    pub body: String,
+}
+
+impl Receipt {
+    pub fn ensure_success(&self) -> Result<(), ApiError> {
+        check_status("entries-upsert-one", self.status_code, &self.body)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Api {
     endpoint: String,
     api_key: String,
+    retry: Retry,
 }
 
 impl Api {
@@ -47,43 +227,49 @@ impl Api {
         Self {
             endpoint: config.api.endpoint.clone(),
             api_key,
+            retry: Retry::default(),
         }
     }
 
+    /// An API client for the given endpoint and key, used to check a key before it is stored
+    pub fn with_key(endpoint: &str, api_key: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            api_key: api_key.to_string(),
+            retry: Retry::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Gets and decodes the url, retrying transient failures
+    fn get<T: DeserializeOwned>(&self, url: &str) -> Result<T, ApiError> {
+        self.retry.run(|| {
+            let response = minreq::get(url)
+                .with_timeout(TIMEOUT)
+                .with_header("X-API-KEY", &self.api_key)
+                .send()
+                .map_err(|err| ApiError::from_minreq(url, err))?;
+            let body = response.as_str().unwrap_or_default();
+            check_status(url, response.status_code, body)?;
+            decode(body)
+        })
+    }
+
     pub fn update_config(&mut self, config: &Config) {
         self.endpoint = config.api.endpoint.clone();
         self.api_key = config.api.key.clone();
     }
 
-    /// Get all months for given year
-    pub fn get_year(
-        &self,
-        sender: &Sender<Message>,
-        now: DateTime<chrono::Utc>,
-        year: u32,
-    ) -> color_eyre::Result<Vec<TimetEntry>> {
-        let mut months = vec![];
-        let month = now.month();
-        // TODO: this can potentially break...
-        for i in 1..=month {
-            months.append(&mut self.get_month(year, i)?);
-            sender.send(Message::RefreshProgressing(i))?;
-        }
-
-        Ok(months)
-    }
     /// Returns a vec of all existing entries for the given year and month.
     /// If the result is empty an empty list is returned ()
     pub fn get_month(&self, year: u32, month: u32) -> color_eyre::Result<Vec<TimetEntry>> {
         let url = format!("{}entries-bymonth?year={year}&month={month}", self.endpoint);
 
-        match minreq::get(&url)
-            .with_timeout(5)
-            .with_header("X-API-KEY", &self.api_key)
-            .send()?
-            .json::<TimetEntries>()?
-            .entries
-        {
+        match self.get::<TimetEntries>(&url)?.entries {
             Some(e) => Ok(e),
             None => Err(eyre!(
                 "No entries in response {} for m{}/y{}",
@@ -94,30 +280,130 @@ impl Api {
         }
     }
 
-    pub fn post_hours(&self, hours: &Hours) -> color_eyre::Result<()> {
-        let response = minreq::post(format!("{}entries-upsert-one", self.endpoint))
-            .with_timeout(5)
-            .with_header("X-API-KEY", &self.api_key)
-            .with_json(hours)?
-            .send()?;
+    /// Returns the catalogue of projects available to the user, including archived projects
+    pub fn get_projects(&self) -> color_eyre::Result<Vec<TimetProject>> {
+        let url = format!("{}projects", self.endpoint);
 
-        if response.status_code != 200 {
-            return Err(eyre!(
-                "respone contains invalid or unexpected status code\n{:?}\n{:?}",
-                response.status_code,
-                response.as_str()
-            ));
+        match self.get::<TimetProjects>(&url)?.projects {
+            Some(p) => Ok(p),
+            None => Err(eyre!("No projects in response {}", url)),
         }
+    }
+
+    /// Posts the hours, returning the response whatever its status code.
+    /// Server errors are retried since the upsert can safely be repeated.
+    /// Check the receipt with [`Receipt::ensure_success`].
+    pub fn post_hours(&self, hours: &Hours) -> color_eyre::Result<Receipt> {
+        let url = format!("{}entries-upsert-one", self.endpoint);
+        let body = serde_json::to_string(hours)?;
+
+        let mut receipt = None;
+        let result = self.retry.run(|| {
+            let response = minreq::post(&url)
+                .with_timeout(TIMEOUT)
+                .with_header("X-API-KEY", &self.api_key)
+                .with_header("Content-Type", "application/json")
+                .with_body(body.as_str())
+                .send()
+                .map_err(|err| ApiError::from_minreq(&url, err))?;
+            let r = Receipt {
+                status_code: response.status_code,
+                body: response.as_str().unwrap_or_default().to_string(),
+            };
+            let status = r.ensure_success();
+            receipt = Some(r);
+            status
+        });
 
-        Ok(())
+        match receipt {
+            Some(receipt) => Ok(receipt),
+            None => Err(result
+                .expect_err("a receipt is kept for every response")
+                .into()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::api::{Api, Hours};
+    use std::time::Duration;
+
+    use crate::api::{check_status, decode, Api, ApiError, Hours, Retry, TimetProjects};
     use crate::config::{self, Config};
 
+    #[test]
+    fn test_check_status() {
+        assert!(check_status("u", 200, "").is_ok());
+        assert_eq!(
+            check_status("u", 401, ""),
+            Err(ApiError::Auth { status: 401 })
+        );
+        assert!(matches!(
+            check_status("u", 404, ""),
+            Err(ApiError::NotFound { .. })
+        ));
+        assert!(check_status("u", 503, "unavailable")
+            .unwrap_err()
+            .is_transient());
+        assert!(!check_status("u", 400, "bad request")
+            .unwrap_err()
+            .is_transient());
+    }
+
+    #[test]
+    fn test_decode() {
+        let projects: TimetProjects = decode(r#"{"projects": []}"#).unwrap();
+        assert_eq!(projects.projects.unwrap().len(), 0);
+
+        let body = format!("<html>{}</html>", "x".repeat(500));
+        match decode::<TimetProjects>(&body) {
+            Err(ApiError::Decode { excerpt, .. }) => {
+                assert!(excerpt.starts_with("<html>"));
+                assert_eq!(excerpt.len(), 120);
+            }
+            other => panic!("expected decode error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_retry() {
+        let retry = Retry {
+            attempts: 3,
+            delay: Duration::ZERO,
+        };
+
+        let mut calls = 0;
+        let result = retry.run(|| {
+            calls += 1;
+            match calls {
+                1 | 2 => Err(ApiError::Timeout {
+                    url: "u".to_string(),
+                }),
+                _ => Ok(calls),
+            }
+        });
+        assert_eq!(result, Ok(3));
+
+        let mut calls = 0;
+        let result: Result<(), ApiError> = retry.run(|| {
+            calls += 1;
+            Err(ApiError::Auth { status: 401 })
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1, "permanent failures are not retried");
+
+        let mut calls = 0;
+        let result: Result<(), ApiError> = retry.run(|| {
+            calls += 1;
+            Err(ApiError::Server {
+                status: 500,
+                excerpt: String::new(),
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+    }
+
     #[ignore = "integration tests not yet planned"]
     #[test]
     fn post_hours_test() {
@@ -129,7 +415,20 @@ mod tests {
             api: config::Api {
                 endpoint: std::env::var("TIMET_API_ENDPOINT").unwrap(),
                 key: std::env::var("TIMET_API_KEY").unwrap(),
+                key_command: None,
+                key_file: None,
             },
+            git: config::Git::default(),
+            groups: std::collections::BTreeMap::new(),
+            holidays: vec![],
+            recurring: vec![],
+            profiles: std::collections::BTreeMap::new(),
+            profile: None,
+            keys: crate::keys::KeyConfig::new(),
+            theme: crate::theme::ThemeConfig::default(),
+            keymap: crate::keys::Keymap::default(),
+            styles: crate::theme::Theme::default(),
+            refresh_interval: None,
         };
 
         let api = Api::new(&cfg);
@@ -140,6 +439,6 @@ mod tests {
             hours: 0.0,
         });
 
-        assert!(res.is_ok())
+        assert!(res.unwrap().ensure_success().is_ok())
     }
 }
@@ -1,9 +1,15 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::{NaiveDate, Weekday};
 use color_eyre::eyre::eyre;
-use color_eyre::{Report, Result, Section};
-use log::error;
-use serde::Deserialize;
+use color_eyre::{eyre::WrapErr, Report, Result, Section};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::keys::{KeyConfig, Keymap};
+use crate::theme::{Theme, ThemeConfig};
+
 pub mod setup;
 
 #[derive(Error, Debug)]
@@ -23,6 +29,12 @@ pub enum ConfigError {
     #[error("TIMET_API_KEY not set")]
     UnsetApiKey,
 
+    #[error("API key command '{command}' failed: {message}")]
+    KeyCommand { command: String, message: String },
+
+    #[error("API key file '{path}' failed: {message}")]
+    KeyFile { path: String, message: String },
+
     #[error(transparent)]
     Fatal(Report),
 }
@@ -37,6 +49,69 @@ pub struct Config {
     pub config_location: String,
     pub default_project: Option<String>,
     pub api: Api,
+    #[serde(default)]
+    pub git: Git,
+    /// Named groups of project ids which can be used as the active filter
+    #[serde(default)]
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Days off which are skipped when copying hours from a previous week
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+    /// Standing commitments proposed as hours to log
+    #[serde(default)]
+    pub recurring: Vec<Recurring>,
+    /// Named accounts, each with its own endpoint, API key and database
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// The profile in use, if any
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Key bindings replacing the defaults, per context
+    #[serde(default)]
+    pub keys: KeyConfig,
+    /// Colour theme and its overrides
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// The key bindings built from `keys` on top of the defaults
+    #[serde(skip)]
+    pub keymap: Keymap,
+    /// The styles built from `theme`
+    #[serde(skip)]
+    pub styles: Theme,
+    /// Syncs with the API in the background this often, e.g. `15m`
+    #[serde(default, deserialize_with = "refresh_interval")]
+    pub refresh_interval: Option<Duration>,
+}
+
+/// A group without projects would filter nothing, showing every project instead
+fn validate_groups(groups: &BTreeMap<String, Vec<String>>) -> Result<(), Report> {
+    match groups
+        .iter()
+        .find(|(_, project_ids)| project_ids.is_empty())
+    {
+        Some((name, _)) => Err(eyre!("Group '{name}' has no projects").suggestion(format!(
+            "Add project ids to '{name}' in the [groups] of config.toml"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Shorter intervals would mostly keep the API busy
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Reads a human readable interval such as `15m` or `1h 30m`
+fn refresh_interval<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    let interval = humantime::parse_duration(&value).map_err(serde::de::Error::custom)?;
+    if interval < MIN_REFRESH_INTERVAL {
+        return Err(serde::de::Error::custom(format!(
+            "refresh_interval '{value}' is shorter than 1m"
+        )));
+    }
+    Ok(Some(interval))
 }
 
 #[derive(Deserialize, Debug)]
@@ -44,25 +119,167 @@ pub struct Api {
     #[serde(skip)]
     pub key: String,
     pub endpoint: String,
+    /// Command printing the API key on stdout, e.g. `pass show timet`
+    pub key_command: Option<String>,
+    /// File containing the API key, only readable by the owner
+    pub key_file: Option<String>,
+}
+
+/// An account replacing the endpoint and default project of the configuration
+#[derive(Deserialize, Debug, Clone)]
+pub struct Profile {
+    pub endpoint: String,
+    /// Name of the keyring entry holding the API key, defaults to `timet-tui-<profile>`
+    pub keyring: Option<String>,
+    pub key_command: Option<String>,
+    pub key_file: Option<String>,
+    pub default_project: Option<String>,
+}
+
+/// Local git repositories used to suggest hours from commit activity
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct Git {
+    /// Author to match commits against, defaults to `user.email` of each repository
+    pub author: Option<String>,
+    #[serde(default)]
+    pub repositories: Vec<Repository>,
+}
+
+/// Maps a repository on disk to the project its commits belong to
+#[derive(Deserialize, Debug, Clone)]
+pub struct Repository {
+    pub path: String,
+    pub project: String,
+}
+
+/// Hours which are logged to a project on a schedule, either on the given `weekdays`
+/// or following an RRULE-like `rule` such as `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO`
+#[derive(Deserialize, Debug, Clone)]
+pub struct Recurring {
+    pub project: String,
+    pub hours: f32,
+    #[serde(default)]
+    pub weekdays: Vec<Weekday>,
+    pub rule: Option<String>,
+    pub start: NaiveDate,
+    pub end: Option<NaiveDate>,
 }
 
 pub static VERSION: &str = env!("CARGO_PKG_VERSION");
 pub static COMMIT: &str = env!("GIT_COMMIT_HASH");
 
 impl Config {
-    pub fn new() -> Result<Self, Report> {
+    /// Reads the configuration including the API key
+    pub fn new(profile: Option<&str>) -> Result<Self, Report> {
+        let mut cfg = Config::load(profile)?;
+        cfg.api.key = cfg.api_key()?;
+
+        Ok(cfg)
+    }
+
+    /// Reads the configuration without the API key, for setting up the key itself
+    pub fn load(profile: Option<&str>) -> Result<Self, Report> {
         let path = locate_config()?;
-        let file = &std::fs::read_to_string(format!("{}/config.toml", &path))
-            .suggestion("Make sure config.toml is available either at $XDG_CONFIG_HOME or at ~/.config/timet/config.toml")?;
+        let config_file = format!("{path}/config.toml");
+        let file = &std::fs::read_to_string(&config_file)
+            .wrap_err(format!("Unable to read {config_file}"))
+            .suggestion("Run `timet-tui init` to create the configuration")?;
         let mut cfg = toml::from_str::<Config>(file)?;
         cfg.config_location = path;
         cfg.api.endpoint = set_endpoint(&cfg.api.endpoint)?;
-        cfg.api.key = get_api_key()?;
+        validate_groups(&cfg.groups)?;
+        crate::recurring::validate(&cfg.recurring)?;
+        cfg.keymap = Keymap::new(&cfg.keys)?;
+        cfg.styles = Theme::new(&cfg.theme)?;
+        if let Some(name) = profile {
+            cfg.use_profile(name)?;
+        }
         cfg.version = VERSION;
         cfg.commit = COMMIT;
 
         Ok(cfg)
     }
+
+    fn use_profile(&mut self, name: &str) -> Result<(), Report> {
+        let profile = self.profiles.get(name).cloned().ok_or_else(|| {
+            eyre!("Unknown profile '{name}'")
+                .suggestion(format!("Add [profiles.{name}] to config.toml"))
+        })?;
+        self.api.endpoint = set_endpoint(&profile.endpoint)?;
+        self.api.key_command = profile.key_command;
+        self.api.key_file = profile.key_file;
+        self.default_project = profile.default_project;
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Reads the API key from the first configured source, in order of precedence:
+    /// the environment variable of the profile, `key_command`, `key_file` and finally the keyring
+    fn api_key(&self) -> Result<String, Report> {
+        if let Ok(local_api_key) = std::env::var(self.api_key_variable()) {
+            return Ok(local_api_key);
+        }
+        if let Some(command) = &self.api.key_command {
+            return Ok(key_from_command(command)?);
+        }
+        if let Some(path) = &self.api.key_file {
+            return Ok(key_from_file(path)?);
+        }
+        get_api_key(&self.keyring_entry())
+    }
+
+    /// Name of the environment variable holding the API key of the active profile,
+    /// `TIMET_API_KEY` without a profile and e.g. `TIMET_API_KEY_ACME` for `acme`
+    pub fn api_key_variable(&self) -> String {
+        match &self.profile {
+            Some(name) => {
+                let suffix: String = name
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() {
+                            c.to_ascii_uppercase()
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect();
+                format!("TIMET_API_KEY_{suffix}")
+            }
+            None => "TIMET_API_KEY".to_string(),
+        }
+    }
+
+    /// Name of the keyring entry holding the API key of the active profile
+    pub fn keyring_entry(&self) -> String {
+        match &self.profile {
+            Some(name) => self.profiles[name]
+                .keyring
+                .clone()
+                .unwrap_or_else(|| format!("timet-tui-{name}")),
+            None => "timet-tui".to_string(),
+        }
+    }
+
+    /// Folder holding the database, separate for every profile
+    pub fn data_location(&self) -> String {
+        match &self.profile {
+            Some(name) => format!("{}/{name}", self.config_location),
+            None => self.config_location.clone(),
+        }
+    }
+
+    /// The profile following the active one, wrapping around to no profile
+    pub fn next_profile(&self) -> Option<String> {
+        match &self.profile {
+            None => self.profiles.keys().next().cloned(),
+            Some(current) => self
+                .profiles
+                .keys()
+                .skip_while(|name| *name != current)
+                .nth(1)
+                .cloned(),
+        }
+    }
 }
 
 fn set_endpoint(endpoint: &str) -> Result<String, Report> {
@@ -81,36 +298,189 @@ fn set_endpoint(endpoint: &str) -> Result<String, Report> {
     }
 }
 
-/// Sets the config location by attempting each option
-fn locate_config() -> Result<String, Report> {
-    if let Ok(mut path) = std::env::var("TIMET_CONFIG_HOME") {
-        path.push_str("/timet");
-        Ok(path)
-    } else if let Ok(mut path) = std::env::var("XDG_CONFIG_HOME") {
-        path.push_str("/timet");
-        Ok(path)
-    } else {
-        Err(eyre!("Config path is not set")
-            .suggestion("Either set TIMET_CONFIG_HOME or add timet/config.toml to XDG_CONFIG_HOME"))
+/// Sets the config location by attempting each option, falling back to the
+/// config folder of the platform (e.g. `~/.config` or `~/Library/Application Support`)
+pub fn locate_config() -> Result<String, Report> {
+    config_location(
+        std::env::var("TIMET_CONFIG_HOME").ok(),
+        std::env::var("XDG_CONFIG_HOME").ok(),
+        dirs::config_dir().and_then(|p| p.to_str().map(str::to_string)),
+    )
+}
+
+/// Picks the first config folder which is set, in the order of [`locate_config`]
+fn config_location(
+    timet_home: Option<String>,
+    xdg_home: Option<String>,
+    platform: Option<String>,
+) -> Result<String, Report> {
+    timet_home
+        .or(xdg_home)
+        .or(platform)
+        .map(|path| format!("{path}/timet"))
+        .ok_or_else(|| {
+            eyre!("Config path is not set").suggestion(
+                "Either set TIMET_CONFIG_HOME or add timet/config.toml to XDG_CONFIG_HOME",
+            )
+        })
+}
+
+/// Whether `config.toml` exists at the config location
+pub fn config_exists() -> bool {
+    locate_config().is_ok_and(|path| std::path::Path::new(&format!("{path}/config.toml")).exists())
+}
+
+/// The answers of the `init` wizard
+#[derive(Clone, PartialEq)]
+pub struct Init {
+    pub endpoint: String,
+    pub default_project: Option<String>,
+    pub api_key: String,
+}
+
+/// Leaves the key out, so it never ends up in a log or an error report
+impl std::fmt::Debug for Init {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Init")
+            .field("endpoint", &self.endpoint)
+            .field("default_project", &self.default_project)
+            .field("api_key", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+struct InitConfig<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_project: Option<&'a str>,
+    api: InitApi<'a>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<&'a str, InitProfile<'a>>,
+}
+
+#[derive(Serialize)]
+struct InitApi<'a> {
+    endpoint: &'a str,
+}
+
+#[derive(Serialize)]
+struct InitProfile<'a> {
+    endpoint: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_project: Option<&'a str>,
+}
+
+/// Creates the config location and writes `config.toml`, refusing to replace an existing one.
+/// With a profile the answers are written as that profile, its endpoint also being the default.
+pub fn write_config(location: &str, init: &Init, profile: Option<&str>) -> Result<(), Report> {
+    let config_file = format!("{location}/config.toml");
+    if std::path::Path::new(&config_file).exists() {
+        return Err(eyre!("{config_file} already exists")
+            .suggestion("Edit the file directly, or remove it and run `timet-tui init` again"));
+    }
+
+    let default_project = init.default_project.as_deref();
+    let content = toml::to_string(&InitConfig {
+        default_project: default_project.filter(|_| profile.is_none()),
+        api: InitApi {
+            endpoint: &init.endpoint,
+        },
+        profiles: profile
+            .map(|name| {
+                let profile = InitProfile {
+                    endpoint: &init.endpoint,
+                    default_project,
+                };
+                BTreeMap::from([(name, profile)])
+            })
+            .unwrap_or_default(),
+    })?;
+    std::fs::create_dir_all(location)?;
+    std::fs::write(&config_file, content)?;
+    Ok(())
+}
+
+/// Replaces a leading `~/` with the home folder
+pub fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
+
+/// Runs the command through the shell and uses the first line of its output as the key
+fn key_from_command(command: &str) -> Result<String, ConfigError> {
+    let failed = |message: String| ConfigError::KeyCommand {
+        command: command.to_string(),
+        message,
+    };
+
+    #[cfg(windows)]
+    let output = std::process::Command::new("cmd")
+        .args(["/C", command])
+        .output();
+    #[cfg(not(windows))]
+    let output = std::process::Command::new("sh")
+        .args(["-c", command])
+        .output();
+
+    let output = output.map_err(|e| failed(e.to_string()))?;
+    if !output.status.success() {
+        return Err(failed(format!(
+            "{} {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let stdout = String::from_utf8(output.stdout).map_err(|e| failed(e.to_string()))?;
+    match stdout.lines().next().map(str::trim) {
+        Some(key) if !key.is_empty() => Ok(key.to_string()),
+        _ => Err(failed("no key on stdout".to_string())),
+    }
+}
+
+/// Reads the key from a file which must not be accessible to group or others
+fn key_from_file(path: &str) -> Result<String, ConfigError> {
+    let failed = |message: String| ConfigError::KeyFile {
+        path: path.to_string(),
+        message,
+    };
+    let expanded = expand_home(path);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&expanded)
+            .map_err(|e| failed(e.to_string()))?
+            .permissions()
+            .mode();
+        if mode & 0o077 != 0 {
+            return Err(failed(format!(
+                "permissions {:o} are too open, run `chmod 600 {path}`",
+                mode & 0o777
+            )));
+        }
+    }
+
+    let content = std::fs::read_to_string(&expanded).map_err(|e| failed(e.to_string()))?;
+    match content.trim() {
+        "" => Err(failed("file is empty".to_string())),
+        key => Ok(key.to_string()),
     }
 }
 
-/// Saves the API key to the secure store
-pub fn set_api_key(api_key: &str) -> Result<(), Report> {
+/// Saves the API key to the secure store under the given entry
+pub fn set_api_key(keyring_entry: &str, api_key: &str) -> Result<(), Report> {
     let user_name = &whoami::username();
-    let entry = keyring::Entry::new("timet-tui", user_name)?;
+    let entry = keyring::Entry::new(keyring_entry, user_name)?;
     entry.set_secret(api_key.as_bytes())?;
     Ok(())
 }
 
 /// Retrieves the API key from the secure store
-pub fn get_api_key() -> Result<String, Report> {
-    if let Ok(local_api_key) = std::env::var("TIMET_API_KEY") {
-        return Ok(local_api_key);
-    }
-
+pub fn get_api_key(keyring_entry: &str) -> Result<String, Report> {
     let user_name = &whoami::username();
-    let entry = keyring::Entry::new("timet-tui", user_name)?;
+    let entry = keyring::Entry::new(keyring_entry, user_name)?;
     let api_key = entry.get_secret().map_err(|e| match e {
         keyring::Error::NoEntry => Report::new(ConfigError::EmptyApiKey)
             .wrap_err(e)
@@ -129,10 +499,43 @@ pub fn get_api_key() -> Result<String, Report> {
     })
 }
 
+#[cfg(test)]
+impl Config {
+    /// A configuration without a file, using the endpoint with a fake key
+    pub(crate) fn test(endpoint: &str) -> Self {
+        Config {
+            version: "0-test",
+            commit: "test",
+            config_location: "/tmp/timet".to_string(),
+            default_project: None,
+            api: Api {
+                key: "test-key".to_string(),
+                endpoint: endpoint.to_string(),
+                key_command: None,
+                key_file: None,
+            },
+            git: Git::default(),
+            groups: BTreeMap::new(),
+            holidays: vec![],
+            recurring: vec![],
+            profiles: BTreeMap::new(),
+            profile: None,
+            keys: KeyConfig::new(),
+            theme: ThemeConfig::default(),
+            keymap: Keymap::default(),
+            styles: Theme::default(),
+            refresh_interval: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::config::{get_api_key, locate_config, set_api_key, set_endpoint, Config};
+    use crate::config::{
+        config_location, get_api_key, key_from_command, key_from_file, set_api_key, set_endpoint,
+        validate_groups, write_config, Config, ConfigError, Init,
+    };
 
     #[test]
     fn set_endpoint_test() {
@@ -154,21 +557,256 @@ mod tests {
 
     #[test]
     fn locate_config_xdg() {
-        std::env::set_var("TIMET_CONFIG_HOME", "config");
-        let location = locate_config().unwrap();
-        std::env::remove_var("TIMET_CONFIG_HOME");
+        let some = |path: &str| Some(path.to_string());
         assert_eq!(
-            "config/timet", location,
-            "config path should match TIMET_CONFIG_HOME is set"
+            "config/timet",
+            config_location(some("config"), some("testdata"), some("home")).unwrap(),
+            "config path should match TIMET_CONFIG_HOME if set"
         );
-
-        std::env::set_var("XDG_CONFIG_HOME", "testdata");
-        let location = locate_config().unwrap();
-        std::env::remove_var("XDG_CONFIG_HOME");
         assert_eq!(
-            "testdata/timet", location,
+            "testdata/timet",
+            config_location(None, some("testdata"), some("home")).unwrap(),
             "config path should match XDG_CONFIG_HOME"
         );
+        assert_eq!(
+            "home/timet",
+            config_location(None, None, some("home")).unwrap(),
+            "config path should fall back to the platform config folder"
+        );
+        assert!(config_location(None, None, None).is_err());
+    }
+
+    #[test]
+    fn write_config_init() {
+        let location = std::env::temp_dir()
+            .join(format!("timet-init-{}", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let init = Init {
+            endpoint: "https://example.org/".to_string(),
+            default_project: Some("mBED9hi2XFs51XzeH0hD".to_string()),
+            api_key: "secret".to_string(),
+        };
+        write_config(&location, &init, None).unwrap();
+
+        let file = std::fs::read_to_string(format!("{location}/config.toml")).unwrap();
+        assert!(!file.contains("secret"), "the key is never written");
+        assert!(!format!("{init:?}").contains("secret"), "nor logged");
+        let cfg = toml::from_str::<Config>(&file).unwrap();
+        assert_eq!(cfg.api.endpoint, "https://example.org/");
+        assert_eq!(cfg.default_project.as_deref(), Some("mBED9hi2XFs51XzeH0hD"));
+
+        assert!(
+            write_config(&location, &init, None).is_err(),
+            "never overwrites"
+        );
+        std::fs::remove_dir_all(&location).unwrap();
+
+        write_config(&location, &init, Some("acme")).unwrap();
+        let file = std::fs::read_to_string(format!("{location}/config.toml")).unwrap();
+        let mut cfg = toml::from_str::<Config>(&file).unwrap();
+        assert_eq!(cfg.default_project, None);
+        cfg.use_profile("acme").unwrap();
+        assert_eq!(cfg.api.endpoint, "https://example.org/");
+        assert_eq!(cfg.default_project.as_deref(), Some("mBED9hi2XFs51XzeH0hD"));
+        assert_eq!(cfg.keyring_entry(), "timet-tui-acme");
+        std::fs::remove_dir_all(&location).unwrap();
+    }
+
+    #[test]
+    fn read_config_git() {
+        let cfg = toml::from_str::<Config>(
+            r#"
+            [api]
+            endpoint = 'asdf'
+
+            [git]
+            author = 'me@example.org'
+
+            [[git.repositories]]
+            path = '~/src/timet-tui'
+            project = 'mBED9hi2XFs51XzeH0hD'
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.git.author.as_deref(), Some("me@example.org"));
+        assert_eq!(cfg.git.repositories.len(), 1);
+        assert_eq!(cfg.git.repositories[0].project, "mBED9hi2XFs51XzeH0hD");
+    }
+
+    #[test]
+    fn read_config_groups() {
+        let cfg = toml::from_str::<Config>(
+            r#"
+            [api]
+            endpoint = 'asdf'
+
+            [groups]
+            "Customer X" = ['mBED9hi2XFs51XzeH0hD', '8oWGyEBxVEyvGw3GZSdp']
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.groups["Customer X"].len(), 2);
+        assert!(validate_groups(&cfg.groups).is_ok());
+
+        let cfg = toml::from_str::<Config>(
+            r#"
+            [api]
+            endpoint = 'asdf'
+
+            [groups]
+            "Customer Y" = []
+            "#,
+        )
+        .unwrap();
+        let err = validate_groups(&cfg.groups).unwrap_err();
+        assert_eq!(err.to_string(), "Group 'Customer Y' has no projects");
+    }
+
+    #[test]
+    fn read_config_recurring() {
+        let cfg = toml::from_str::<Config>(
+            r#"
+            [api]
+            endpoint = 'asdf'
+
+            [[recurring]]
+            project = 'mBED9hi2XFs51XzeH0hD'
+            hours = 1.0
+            weekdays = ['Mon']
+            start = '2024-01-01'
+
+            [[recurring]]
+            project = '8oWGyEBxVEyvGw3GZSdp'
+            hours = 0.5
+            rule = 'FREQ=WEEKLY;INTERVAL=2;BYDAY=FR'
+            start = '2024-01-01'
+            end = '2024-12-31'
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.recurring.len(), 2);
+        assert_eq!(cfg.recurring[0].weekdays, vec![chrono::Weekday::Mon]);
+        assert!(cfg.recurring[1].end.is_some());
+    }
+
+    #[test]
+    fn read_config_refresh_interval() {
+        let read = |interval: &str| {
+            toml::from_str::<Config>(&format!(
+                r#"
+                refresh_interval = '{interval}'
+
+                [api]
+                endpoint = 'asdf'
+                "#
+            ))
+        };
+        assert_eq!(
+            read("1h 30m").unwrap().refresh_interval,
+            Some(std::time::Duration::from_secs(90 * 60))
+        );
+        assert!(read("10s").is_err(), "too short");
+        assert!(read("soon").is_err());
+
+        let cfg = toml::from_str::<Config>("[api]\nendpoint = 'asdf'").unwrap();
+        assert_eq!(cfg.refresh_interval, None);
+    }
+
+    #[test]
+    fn read_config_holidays() {
+        let cfg = toml::from_str::<Config>(
+            r#"
+            holidays = ['2024-12-24', '2024-12-25']
+
+            [api]
+            endpoint = 'asdf'
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.holidays,
+            vec![
+                chrono::NaiveDate::from_ymd_opt(2024, 12, 24).unwrap(),
+                chrono::NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn read_config_profiles() {
+        let mut cfg = toml::from_str::<Config>(
+            r#"
+            [api]
+            endpoint = 'asdf'
+
+            [profiles.acme]
+            endpoint = 'acme.example.org'
+            default_project = 'mBED9hi2XFs51XzeH0hD'
+
+            [profiles.side]
+            endpoint = 'https://side.example.org'
+            keyring = 'side-key'
+            "#,
+        )
+        .unwrap();
+        cfg.config_location = "config/timet".to_string();
+        assert_eq!(cfg.keyring_entry(), "timet-tui");
+        assert_eq!(cfg.api_key_variable(), "TIMET_API_KEY");
+        assert_eq!(cfg.data_location(), "config/timet");
+        assert_eq!(cfg.next_profile().as_deref(), Some("acme"));
+
+        cfg.use_profile("acme").unwrap();
+        assert_eq!(cfg.api.endpoint, "https://acme.example.org/");
+        assert_eq!(cfg.default_project.as_deref(), Some("mBED9hi2XFs51XzeH0hD"));
+        assert_eq!(cfg.keyring_entry(), "timet-tui-acme");
+        assert_eq!(cfg.api_key_variable(), "TIMET_API_KEY_ACME");
+        assert_eq!(cfg.data_location(), "config/timet/acme");
+        assert_eq!(cfg.next_profile().as_deref(), Some("side"));
+
+        cfg.use_profile("side").unwrap();
+        assert_eq!(cfg.keyring_entry(), "side-key");
+        assert_eq!(cfg.api_key_variable(), "TIMET_API_KEY_SIDE");
+        assert_eq!(cfg.next_profile(), None, "wraps around to no profile");
+
+        assert!(cfg.use_profile("unknown").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn key_command() {
+        assert_eq!(key_from_command("echo ' secret '").unwrap(), "secret");
+        assert!(matches!(
+            key_from_command("exit 3"),
+            Err(ConfigError::KeyCommand { .. })
+        ));
+        assert!(
+            key_from_command("true").is_err(),
+            "empty output is an error"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn key_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("timet-key-{}", std::process::id()));
+        std::fs::write(&path, "secret\n").unwrap();
+        let location = path.to_str().unwrap();
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        match key_from_file(location) {
+            Err(ConfigError::KeyFile { message, .. }) => assert!(message.contains("644")),
+            other => panic!("expected permission error, got {other:?}"),
+        }
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(key_from_file(location).unwrap(), "secret");
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(key_from_file(location).is_err());
     }
 
     #[test]
@@ -182,10 +820,10 @@ mod tests {
     #[ignore = "testing of secure store not required (at the moment)"]
     #[test]
     fn test_keyring() {
-        let result = set_api_key("test-123");
+        let result = set_api_key("timet-tui", "test-123");
         assert!(result.ok().is_some());
 
-        if let Ok(secret) = get_api_key() {
+        if let Ok(secret) = get_api_key("timet-tui") {
             assert_eq!(secret, "test-123".to_string(), "error: {}", secret);
         }
     }
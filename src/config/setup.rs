@@ -1,18 +1,103 @@
+use chrono::{Datelike, Local};
 use crossterm::event::{self, Event, KeyCode};
 use eyre::Report;
+use log::warn;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     prelude::Backend,
-    style::{palette::tailwind, Modifier, Style},
     text::Line,
-    widgets::{Block, BorderType, Borders, Paragraph},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
     Terminal,
 };
 
-use crate::ui::{BORDER_COLOR, POPUP_STYLE};
+use super::Init;
+use crate::api::{self, Api, Retry, TimetEntry, TimetProject};
+use crate::theme::Theme;
 
-pub fn enter_api_key(terminal: &mut Terminal<impl Backend>) -> Result<String, Report> {
+/// Outcome of checking the entered key against the endpoint
+enum Status {
+    Editing,
+    Checking,
+    /// Accepted, with the number of projects when the endpoint lists them
+    Accepted(Option<usize>),
+    Rejected(String),
+}
+
+/// Checks the key by fetching the entries of the current month, also returning the
+/// project catalogue when the endpoint provides it
+fn check_api_key(
+    endpoint: &str,
+    api_key: &str,
+) -> Result<(Vec<TimetEntry>, Option<Vec<TimetProject>>), String> {
+    if api_key.trim().is_empty() {
+        return Err("API key is empty".to_string());
+    }
+    let api = Api::with_key(endpoint, api_key.trim()).with_retry(Retry {
+        attempts: 1,
+        ..Retry::default()
+    });
+    let today = Local::now().date_naive();
+    let entries = api
+        .get_month(today.year() as u32, today.month())
+        .map_err(|err| api::describe(&err))?;
+    let projects = api
+        .get_projects()
+        .inspect_err(|err| warn!("Fetching projects: {err:?}"))
+        .ok();
+    Ok((entries, projects))
+}
+
+/// The projects to pick the default from, the projects logged this month when the
+/// endpoint does not list its catalogue
+fn known_projects(
+    entries: Vec<TimetEntry>,
+    projects: Option<Vec<TimetProject>>,
+) -> Vec<TimetProject> {
+    projects.unwrap_or_else(|| {
+        let mut logged: Vec<TimetProject> = Vec::new();
+        for entry in entries {
+            if !logged.iter().any(|p| p.project_id == entry.project_id) {
+                logged.push(TimetProject {
+                    project_id: entry.project_id,
+                    project_name: entry.project_name,
+                    archived: false,
+                });
+            }
+        }
+        logged
+    })
+}
+
+/// Describes the accepted key or configuration, with the number of projects when known
+fn accepted(what: &str, projects: Option<usize>) -> String {
+    match projects {
+        Some(projects) => format!("{what} accepted ({projects} projects available)"),
+        None => format!("{what} accepted"),
+    }
+}
+
+/// Finds the project id matching the given id or name, an empty input meaning no default project
+fn resolve_project(projects: &[TimetProject], input: &str) -> Result<Option<String>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    projects
+        .iter()
+        .find(|p| p.project_id == input || p.project_name.eq_ignore_ascii_case(input))
+        .map(|p| Some(p.project_id.clone()))
+        .ok_or_else(|| format!("Project '{input}' not found"))
+}
+
+/// Asks for the API key and checks it against the endpoint before it is returned.
+/// Returns `None` when cancelled, so a working key is not overwritten.
+pub fn enter_api_key(
+    terminal: &mut Terminal<impl Backend>,
+    theme: &Theme,
+    endpoint: &str,
+) -> Result<Option<String>, Report> {
     let mut input = String::new();
+    let mut status = Status::Editing;
     loop {
         terminal.draw(|f| {
             let area = f.area();
@@ -22,7 +107,7 @@ pub fn enter_api_key(terminal: &mut Terminal<impl Backend>) -> Result<String, Re
             let container = Block::default()
                 .borders(Borders::ALL)
                 .title("Timet.io Credentials")
-                .border_style(BORDER_COLOR)
+                .border_style(theme.border)
                 .border_type(BorderType::Rounded);
             f.render_widget(&container, popup_area);
 
@@ -32,7 +117,7 @@ pub fn enter_api_key(terminal: &mut Terminal<impl Backend>) -> Result<String, Re
                 .constraints([
                     Constraint::Length(2),
                     Constraint::Length(3),
-                    Constraint::Length(1),
+                    Constraint::Length(2),
                     Constraint::Length(5),
                 ])
                 .margin(1)
@@ -41,45 +126,260 @@ pub fn enter_api_key(terminal: &mut Terminal<impl Backend>) -> Result<String, Re
             let masked = "*".repeat(input.len());
             let pw = Paragraph::new(masked).block(
                 Block::default()
-                    .border_style(BORDER_COLOR)
+                    .border_style(theme.border)
                     .borders(Borders::ALL)
                     .title("API Key"),
             );
             f.render_widget(pw, chunks[1]);
 
+            let status_line = match &status {
+                Status::Editing => Line::from(""),
+                Status::Checking => Line::from(format!("Checking key against {endpoint}...")),
+                Status::Accepted(projects) => {
+                    Line::from(accepted("Key", *projects)).style(theme.success)
+                }
+                Status::Rejected(reason) => Line::from(reason.as_str()).style(theme.error),
+            };
+            f.render_widget(
+                Paragraph::new(status_line)
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true }),
+                chunks[2],
+            );
+
+            let action = match status {
+                Status::Accepted(_) => "<Enter> save   <Esc> cancel",
+                _ => "<Enter> check   <Esc> cancel",
+            };
             let help_text = vec![
                 Line::from("Where do I find my API Key?"),
                 Line::from("Go to timet -> Settings -> API keys -> Generate"),
                 Line::from("Paste key into this window and press"),
-                Line::from("<Enter> ").centered().style(POPUP_STYLE),
+                Line::from(action).centered().style(theme.popup),
             ];
             let footer = Paragraph::new(help_text)
                 .alignment(Alignment::Center)
-                .style(
-                    Style::default()
-                        .add_modifier(Modifier::BOLD)
-                        .fg(tailwind::RED.c200),
-                );
+                .style(theme.hint);
 
             f.render_widget(footer, chunks[3]);
         })?;
 
+        if let Status::Checking = status {
+            status = match check_api_key(endpoint, &input) {
+                Ok((_, projects)) => Status::Accepted(projects.map(|p| p.len())),
+                Err(reason) => Status::Rejected(reason),
+            };
+            continue;
+        }
+
         if event::poll(std::time::Duration::from_millis(60))? {
             if let Event::Key(k) = event::read()? {
                 match k.code {
-                    KeyCode::Esc => break,
-                    KeyCode::Enter => break,
+                    KeyCode::Esc => return Ok(None),
+                    KeyCode::Enter => match status {
+                        Status::Accepted(_) => return Ok(Some(input.trim().to_string())),
+                        _ => status = Status::Checking,
+                    },
                     KeyCode::Backspace => {
                         input.pop();
+                        status = Status::Editing;
+                    }
+                    KeyCode::Char(c) => {
+                        input.push(c);
+                        status = Status::Editing;
                     }
-                    KeyCode::Char(c) => input.push(c),
                     _ => {}
                 }
             }
         }
     }
+}
+
+/// The fields of the `init` form, in order
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Endpoint,
+    Project,
+    Key,
+}
+
+impl Field {
+    fn next(self) -> Self {
+        match self {
+            Field::Endpoint => Field::Project,
+            Field::Project => Field::Key,
+            Field::Key => Field::Endpoint,
+        }
+    }
 
-    Ok(input)
+    fn previous(self) -> Self {
+        match self {
+            Field::Endpoint => Field::Key,
+            Field::Project => Field::Endpoint,
+            Field::Key => Field::Project,
+        }
+    }
+}
+
+/// Checks the answers of the `init` form against the endpoint,
+/// also returning the number of projects available when the endpoint lists them
+fn check_init(
+    endpoint: &str,
+    project: &str,
+    api_key: &str,
+) -> Result<(Init, Option<usize>), String> {
+    let endpoint = super::set_endpoint(endpoint.trim()).map_err(|err| err.to_string())?;
+    let (entries, projects) = check_api_key(&endpoint, api_key)?;
+    let count = projects.as_ref().map(Vec::len);
+    let init = Init {
+        default_project: resolve_project(&known_projects(entries, projects), project)?,
+        endpoint,
+        api_key: api_key.trim().to_string(),
+    };
+    Ok((init, count))
+}
+
+/// Asks for the endpoint, the default project and the API key for a new configuration,
+/// checking them with a test request before they are returned.
+/// Returns `None` when cancelled.
+pub fn init(
+    terminal: &mut Terminal<impl Backend>,
+    theme: &Theme,
+    location: &str,
+) -> Result<Option<Init>, Report> {
+    let mut endpoint = String::new();
+    let mut project = String::new();
+    let mut input = String::new();
+    let mut field = Field::Endpoint;
+    let mut status = Status::Editing;
+    let mut checked = None;
+    loop {
+        terminal.draw(|f| {
+            let popup_area = centered_rect(50, 50, f.area());
+
+            let container = Block::default()
+                .borders(Borders::ALL)
+                .title("Timet.io Configuration")
+                .border_style(theme.border)
+                .border_type(BorderType::Rounded);
+            f.render_widget(&container, popup_area);
+
+            let inner = container.inner(popup_area);
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(2),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(2),
+                    Constraint::Length(3),
+                ])
+                .margin(1)
+                .split(inner);
+
+            f.render_widget(
+                Paragraph::new(format!("Creating {location}/config.toml"))
+                    .alignment(Alignment::Center),
+                chunks[0],
+            );
+
+            let masked = "*".repeat(input.len());
+            let inputs = [
+                (Field::Endpoint, "Endpoint", endpoint.as_str()),
+                (
+                    Field::Project,
+                    "Default project (optional)",
+                    project.as_str(),
+                ),
+                (Field::Key, "API Key", masked.as_str()),
+            ];
+            for (i, (f_field, title, value)) in inputs.into_iter().enumerate() {
+                let style = if f_field == field {
+                    theme.popup
+                } else {
+                    theme.border
+                };
+                let paragraph = Paragraph::new(value).block(
+                    Block::default()
+                        .border_style(style)
+                        .borders(Borders::ALL)
+                        .title(title),
+                );
+                f.render_widget(paragraph, chunks[i + 1]);
+            }
+
+            let status_line = match &status {
+                Status::Editing => Line::from(""),
+                Status::Checking => Line::from("Checking configuration..."),
+                Status::Accepted(projects) => {
+                    Line::from(accepted("Configuration", *projects)).style(theme.success)
+                }
+                Status::Rejected(reason) => Line::from(reason.as_str()).style(theme.error),
+            };
+            f.render_widget(
+                Paragraph::new(status_line)
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true }),
+                chunks[4],
+            );
+
+            let action = match status {
+                Status::Accepted(_) => "<Enter> save   <Esc> cancel",
+                _ => "<Tab> next field   <Enter> check   <Esc> cancel",
+            };
+            let help_text = vec![
+                Line::from("The API key is created in timet -> Settings -> API keys -> Generate"),
+                Line::from(action).centered().style(theme.popup),
+            ];
+            f.render_widget(
+                Paragraph::new(help_text)
+                    .alignment(Alignment::Center)
+                    .style(theme.hint),
+                chunks[5],
+            );
+        })?;
+
+        if let Status::Checking = status {
+            status = match check_init(&endpoint, &project, &input) {
+                Ok((init, projects)) => {
+                    checked = Some(init);
+                    Status::Accepted(projects)
+                }
+                Err(reason) => Status::Rejected(reason),
+            };
+            continue;
+        }
+
+        if event::poll(std::time::Duration::from_millis(60))? {
+            if let Event::Key(k) = event::read()? {
+                let value = match field {
+                    Field::Endpoint => &mut endpoint,
+                    Field::Project => &mut project,
+                    Field::Key => &mut input,
+                };
+                match k.code {
+                    KeyCode::Esc => return Ok(None),
+                    KeyCode::Tab | KeyCode::Down => field = field.next(),
+                    KeyCode::BackTab | KeyCode::Up => field = field.previous(),
+                    KeyCode::Enter => match status {
+                        Status::Accepted(_) => return Ok(checked),
+                        _ if field != Field::Key => field = field.next(),
+                        _ => status = Status::Checking,
+                    },
+                    KeyCode::Backspace => {
+                        value.pop();
+                        status = Status::Editing;
+                    }
+                    KeyCode::Char(c) => {
+                        value.push(c);
+                        status = Status::Editing;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
@@ -101,3 +401,89 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Local};
+
+    use crate::api::{TimetEntry, TimetProject};
+    use crate::config::setup::{check_api_key, known_projects, resolve_project};
+    use crate::harness::FakeApi;
+
+    #[test]
+    fn test_check_api_key() {
+        let api = FakeApi::start();
+        let (entries, projects) = check_api_key(&api.endpoint, " key ").unwrap();
+        assert!(entries.is_empty());
+        assert_eq!(projects.map(|p| p.len()), Some(4));
+
+        let today = Local::now().date_naive();
+        let month = format!(
+            "/entries-bymonth?year={}&month={}",
+            today.year(),
+            today.month()
+        );
+        assert_eq!(
+            api.requests("GET")[0].path,
+            month,
+            "the key is checked on this month"
+        );
+
+        api.fail("/projects");
+        let (_, projects) = check_api_key(&api.endpoint, "key").unwrap();
+        assert!(projects.is_none(), "the catalogue is optional");
+
+        api.fail(&month);
+        assert!(check_api_key(&api.endpoint, "key").is_err());
+        assert!(check_api_key(&api.endpoint, " ").is_err());
+    }
+
+    #[test]
+    fn test_known_projects() {
+        let entry = |day_of_year, project_id: &str, project_name: &str| TimetEntry {
+            day_of_year,
+            year: 2024,
+            month: 4,
+            iso_week_year: 2024,
+            iso_week: 17,
+            week: 17,
+            hours: 7.5,
+            project_name: project_name.to_string(),
+            project_id: project_id.to_string(),
+        };
+        let entries = vec![
+            entry(113, "mBED9hi2XFs51XzeH0hD", "Project A"),
+            entry(114, "mBED9hi2XFs51XzeH0hD", "Project A"),
+            entry(114, "QOaRvlDz2RVfzDf8thet", "Project B"),
+        ];
+        let projects = known_projects(entries, None);
+        assert_eq!(
+            projects.len(),
+            2,
+            "the projects logged this month, once each"
+        );
+        assert_eq!(
+            resolve_project(&projects, "project b"),
+            Ok(Some("QOaRvlDz2RVfzDf8thet".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_resolve_project() {
+        let projects = vec![TimetProject {
+            project_id: "mBED9hi2XFs51XzeH0hD".to_string(),
+            project_name: "Project A".to_string(),
+            archived: false,
+        }];
+        assert_eq!(resolve_project(&projects, " "), Ok(None));
+        assert_eq!(
+            resolve_project(&projects, "project a"),
+            Ok(Some("mBED9hi2XFs51XzeH0hD".to_string()))
+        );
+        assert_eq!(
+            resolve_project(&projects, "mBED9hi2XFs51XzeH0hD"),
+            Ok(Some("mBED9hi2XFs51XzeH0hD".to_string()))
+        );
+        assert!(resolve_project(&projects, "Project B").is_err());
+    }
+}
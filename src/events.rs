@@ -0,0 +1,73 @@
+//! Threads feeding the channel of the event loop, next to the background tasks of the model
+
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use color_eyre::eyre::WrapErr;
+use crossterm::event::{self, Event, KeyEventKind, MouseEventKind};
+
+use crate::model::Message;
+use crate::task::{self, Task};
+
+/// How often the clock is sent to the event loop
+pub const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// Reads terminal events until the event loop is gone, reporting a failure to read them
+pub fn spawn_input(sender: Sender<Message>) {
+    task::spawn(Task::Input, sender.clone(), move || loop {
+        let event = event::read().wrap_err("Reading terminal events")?;
+        if relevant(&event) && sender.send(Message::Input(event)).is_err() {
+            return Ok(());
+        }
+    });
+}
+
+/// Sends the time every `rate` until the event loop is gone
+pub fn spawn_ticks(sender: Sender<Message>, rate: Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(rate);
+        if sender.send(Message::Tick(Utc::now())).is_err() {
+            return;
+        }
+    });
+}
+
+/// Key releases and mouse movement do nothing, leave them out to avoid drawing for them
+fn relevant(event: &Event) -> bool {
+    match event {
+        Event::Key(key) => key.kind == KeyEventKind::Press,
+        Event::Mouse(mouse) => !matches!(mouse.kind, MouseEventKind::Moved),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
+    };
+
+    use crate::events::relevant;
+
+    #[test]
+    fn test_relevant() {
+        let mut key = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert!(relevant(&Event::Key(key)));
+        key.kind = KeyEventKind::Release;
+        assert!(!relevant(&Event::Key(key)));
+
+        let mouse = |kind| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column: 0,
+                row: 0,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+        assert!(!relevant(&mouse(MouseEventKind::Moved)));
+        assert!(relevant(&mouse(MouseEventKind::ScrollDown)));
+        assert!(relevant(&Event::Resize(80, 24)));
+    }
+}
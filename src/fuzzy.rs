@@ -0,0 +1,56 @@
+/// Scores how well `query` matches `candidate` as a case-insensitive subsequence.
+/// Returns `None` when not every character of the query can be found in order.
+/// Consecutive characters and characters at the start of a word score higher,
+/// so `pa` ranks `Project A` below `Payroll`.
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = candidate[position..].iter().position(|c| *c == q)? + position;
+
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !candidate[found - 1].is_alphanumeric() {
+            score += 10;
+        }
+        // penalise gaps between matched characters
+        score -= (found - previous.map_or(0, |p| p + 1)).min(10) as i64;
+
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fuzzy::score;
+
+    #[test]
+    fn test_score_matches() {
+        assert_eq!(score("", "Project A"), Some(0));
+        assert!(score("pra", "Project A").is_some());
+        assert!(score("PRA", "project a").is_some(), "case insensitive");
+        assert!(score("apr", "Project A").is_none(), "order matters");
+        assert!(score("projectx", "Project A").is_none());
+    }
+
+    #[test]
+    fn test_score_ranking() {
+        let word_start = score("pa", "Payroll").unwrap();
+        let scattered = score("pa", "Project A").unwrap();
+        let mid_word = score("pa", "Empathy").unwrap();
+        assert!(word_start > scattered, "{word_start} > {scattered}");
+        assert!(scattered > mid_word, "{scattered} > {mid_word}");
+    }
+}
@@ -0,0 +1,316 @@
+//! Drives the whole application in tests: a model over an in-memory store and a fake API,
+//! updated with messages and keys like the event loop does, rendered to a test terminal.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{Datelike, TimeZone, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+use crate::api::{Api, TimetEntries, TimetProjects};
+use crate::config::Config;
+use crate::model::{Message, Model};
+use crate::store::Store;
+use crate::ui::view;
+use crate::update::{handle_key, update};
+
+/// How long to wait for a message from a background thread
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request received by the fake API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+/// Serves the fixtures of `testdata` like the timet API, recording every request.
+/// April 2024 has the entries of `april.json`, every other month is empty.
+pub(crate) struct FakeApi {
+    pub endpoint: String,
+    pub requests: Arc<Mutex<Vec<Request>>>,
+    behaviour: Arc<Mutex<Behaviour>>,
+}
+
+/// Changes to the responses of the fake API
+#[derive(Debug, Default)]
+struct Behaviour {
+    /// Paths answered with 400 Bad Request
+    failing: Vec<String>,
+    /// Requests answered with 400 Bad Request when their body contains any of these
+    failing_bodies: Vec<String>,
+    /// Wait before answering every request
+    delay: Duration,
+}
+
+impl FakeApi {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let behaviour = Arc::new(Mutex::new(Behaviour::default()));
+        let (recorded, answers) = (requests.clone(), behaviour.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                respond(stream, &recorded, &answers);
+            }
+        });
+        FakeApi {
+            endpoint,
+            requests,
+            behaviour,
+        }
+    }
+
+    /// Answers requests to the path, e.g. `/projects`, with 400 Bad Request
+    pub fn fail(&self, path: &str) {
+        self.behaviour
+            .lock()
+            .unwrap()
+            .failing
+            .push(path.to_string());
+    }
+
+    /// Answers requests whose body contains the text, e.g. a date, with 400 Bad Request
+    pub fn fail_body(&self, text: &str) {
+        self.behaviour
+            .lock()
+            .unwrap()
+            .failing_bodies
+            .push(text.to_string());
+    }
+
+    /// Answers every request normally again
+    pub fn recover(&self) {
+        let mut behaviour = self.behaviour.lock().unwrap();
+        behaviour.failing.clear();
+        behaviour.failing_bodies.clear();
+    }
+
+    pub fn delay(&self, delay: Duration) {
+        self.behaviour.lock().unwrap().delay = delay;
+    }
+
+    pub fn requests(&self, method: &str) -> Vec<Request> {
+        let requests = self.requests.lock().unwrap();
+        requests
+            .iter()
+            .filter(|r| r.method == method)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Records the request before answering it, so it is known once the client has the response
+fn respond(mut stream: TcpStream, recorded: &Mutex<Vec<Request>>, behaviour: &Mutex<Behaviour>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).unwrap();
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    let body = String::from_utf8(body).unwrap();
+
+    let (failing, delay) = {
+        let behaviour = behaviour.lock().unwrap();
+        let failing = behaviour.failing.contains(&path)
+            || behaviour.failing_bodies.iter().any(|b| body.contains(b));
+        (failing, behaviour.delay)
+    };
+    recorded.lock().unwrap().push(Request {
+        method: method.clone(),
+        path: path.clone(),
+        body,
+    });
+    thread::sleep(delay);
+    let status = if failing { "400 Bad Request" } else { "200 OK" };
+
+    let response = match (method.as_str(), path.as_str()) {
+        _ if failing => r#"{"error": "bad request"}"#.to_string(),
+        ("GET", "/projects") => std::fs::read_to_string("testdata/projects.json").unwrap(),
+        ("GET", "/entries-bymonth?year=2024&month=4") => {
+            std::fs::read_to_string("testdata/april.json").unwrap()
+        }
+        ("GET", _) => r#"{"entries": []}"#.to_string(),
+        _ => "{}".to_string(),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+        response.len()
+    )
+    .unwrap();
+}
+
+/// The store with only the projects from `testdata`, no hours were logged yet
+pub(crate) fn catalogue_store() -> Store {
+    let store = Store::memory().unwrap();
+    let projects: TimetProjects =
+        serde_json::from_slice(&std::fs::read("testdata/projects.json").unwrap()).unwrap();
+    store.insert_projects(projects.projects.unwrap()).unwrap();
+    store
+}
+
+/// The store seeded with the projects and the entries of April 2024 from `testdata`
+pub(crate) fn seeded_store() -> Store {
+    let store = catalogue_store();
+    let entries: TimetEntries =
+        serde_json::from_slice(&std::fs::read("testdata/april.json").unwrap()).unwrap();
+    store.insert(entries.entries.unwrap()).unwrap();
+    store
+}
+
+pub(crate) struct Harness {
+    pub model: Model,
+    pub api: FakeApi,
+    receiver: Receiver<Message>,
+}
+
+impl Harness {
+    /// The application on the 30th of April 2024, with the fixtures already in the store
+    pub fn new() -> Self {
+        Self::with_store(seeded_store())
+    }
+
+    pub fn with_store(store: Store) -> Self {
+        Self::build(store, |_| {})
+    }
+
+    /// The application with the fixtures, after changing the configuration
+    pub fn configured(configure: impl FnOnce(&mut Config)) -> Self {
+        Self::build(seeded_store(), configure)
+    }
+
+    fn build(store: Store, configure: impl FnOnce(&mut Config)) -> Self {
+        let api = FakeApi::start();
+        let mut config = Config::test(&api.endpoint);
+        configure(&mut config);
+        let (sender, receiver) = mpsc::channel();
+        let mut model = Model::new(sender, Api::new(&config), store, config).unwrap();
+        model.set_now(Utc.with_ymd_and_hms(2024, 4, 30, 12, 0, 0).unwrap());
+        model.active_year = model.today().year();
+        model.overview = model.store.get_yearly_overview(model.active_year).unwrap();
+        model.set_suggestions().unwrap();
+        model.schedule_refresh();
+        Harness {
+            model,
+            api,
+            receiver,
+        }
+    }
+
+    /// Updates the model with the message and every message following from it
+    pub fn send(&mut self, msg: Message) -> &mut Self {
+        let mut current = Some(msg);
+        while let Some(msg) = current {
+            current = update(&mut self.model, msg).unwrap();
+        }
+        self
+    }
+
+    pub fn key(&mut self, code: KeyCode) -> &mut Self {
+        self.key_event(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    pub fn key_event(&mut self, key: KeyEvent) -> &mut Self {
+        if let Some(msg) = handle_key(key, &mut self.model).unwrap() {
+            self.send(msg);
+        }
+        self
+    }
+
+    pub fn type_str(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            self.key(KeyCode::Char(c));
+        }
+        self
+    }
+
+    /// Applies the messages of background threads until one of them is done
+    pub fn wait_for(&mut self, done: impl Fn(&Message) -> bool) -> &mut Self {
+        loop {
+            let msg = self
+                .receiver
+                .recv_timeout(TIMEOUT)
+                .expect("no message from the background");
+            let last = done(&msg);
+            self.send(msg);
+            if last {
+                return self;
+            }
+        }
+    }
+
+    /// Applies the messages of background threads arriving within the time
+    pub fn drain(&mut self, wait: Duration) -> &mut Self {
+        while let Ok(msg) = self.receiver.recv_timeout(wait) {
+            self.send(msg);
+        }
+        self
+    }
+
+    /// The lines of the view, with the endpoint of the fake API, which changes every run,
+    /// replaced by a placeholder of the same width
+    pub fn render(&mut self, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| view(&mut self.model, f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let placeholder = format!("{:<1$}", "<endpoint>", self.api.endpoint.len());
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .replace(&self.api.endpoint, &placeholder)
+            })
+            .collect()
+    }
+
+    /// Compares the view with `testdata/snapshots/<name>.txt`.
+    /// Set `UPDATE_SNAPSHOTS` to write new snapshots and overwrite the existing ones,
+    /// otherwise a missing snapshot fails the test.
+    pub fn assert_snapshot(&mut self, name: &str, width: u16, height: u16) {
+        let actual = self.render(width, height).join("\n") + "\n";
+        let path = PathBuf::from("testdata/snapshots").join(format!("{name}.txt"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let Ok(expected) = std::fs::read_to_string(&path) else {
+            panic!(
+                "snapshot {} is missing, set UPDATE_SNAPSHOTS=1 to create it\n--- actual\n{actual}",
+                path.display()
+            );
+        };
+        assert!(
+            expected == actual,
+            "snapshot {} differs, set UPDATE_SNAPSHOTS=1 to accept\n--- expected\n{expected}--- actual\n{actual}",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crate::keys::{Action, Context, Keymap};
+use crate::model::{ActiveView, Message};
+use crate::store::{Store, Submission};
+use crate::theme::Theme;
+use color_eyre::Result;
+use crossterm::event::KeyEvent;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{
+    Block, BorderType, Cell, Clear, HighlightSpacing, Paragraph, Row, Table, TableState, Wrap,
+};
+use ratatui::Frame;
+
+/// Number of submissions loaded into the view
+const HISTORY_LIMIT: usize = 500;
+
+#[derive(Debug)]
+pub struct HistoryModel {
+    store: Store,
+    keymap: Keymap,
+    theme: Theme,
+    submissions: Vec<Submission>,
+    table_state: TableState,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub enum HistoryMessage {
+    Open,
+}
+
+impl HistoryModel {
+    pub fn new(store: Store, keymap: Keymap, theme: Theme) -> Self {
+        HistoryModel {
+            store,
+            keymap,
+            theme,
+            submissions: vec![],
+            table_state: TableState::default(),
+        }
+    }
+
+    fn open(&mut self) -> Result<()> {
+        self.submissions = self.store.submissions(HISTORY_LIMIT)?;
+        self.table_state
+            .select((!self.submissions.is_empty()).then_some(0));
+        Ok(())
+    }
+}
+
+pub fn handle_key(key: KeyEvent, model: &mut HistoryModel) -> Result<Option<Message>> {
+    match model.keymap.action(Context::History, &key) {
+        Some(action) => run_action(model, action),
+        None => Ok(None),
+    }
+}
+
+/// Runs an action of the history keys, also when picked in the command palette
+pub fn run_action(model: &mut HistoryModel, action: Action) -> Result<Option<Message>> {
+    match action {
+        Action::Close => Ok(Some(Message::Home)),
+        Action::Down => {
+            model.table_state.select_next();
+            Ok(None)
+        }
+        Action::Up => {
+            model.table_state.select_previous();
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+pub fn update(model: &mut HistoryModel, msg: HistoryMessage) -> Result<Option<Message>> {
+    match msg {
+        HistoryMessage::Open => {
+            model.open()?;
+            Ok(Some(Message::View(ActiveView::History)))
+        }
+    }
+}
+
+pub fn render(f: &mut Frame, model: &mut HistoryModel, area: Rect) {
+    let theme = model.theme;
+    f.render_widget(Clear, area);
+
+    let block = Block::bordered()
+        .border_type(BorderType::Plain)
+        .style(theme.border)
+        .title("Submission history");
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [table_area, detail_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(4)]).areas(inner);
+
+    let header = ["Submitted", "Date", "Project", "Hours", "Status", "Source"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(theme.alternate(1))
+        .bottom_margin(1);
+
+    let rows = model.submissions.iter().enumerate().map(|(i, s)| {
+        let status = match s.status_code {
+            Some(code) => code.to_string(),
+            None => "-".to_string(),
+        };
+        let status_style = match s.status_code {
+            Some(200) => theme.success,
+            _ => theme.error,
+        };
+        Row::new(vec![
+            Cell::from(s.submitted.format("%Y-%m-%d %H:%M:%S").to_string()),
+            Cell::from(s.date.format("%D").to_string()),
+            Cell::from(s.project_name.clone()),
+            Cell::from(format!("{:.1}", s.hours)),
+            Cell::from(status).style(status_style),
+            Cell::from(s.source.clone()),
+        ])
+        .style(theme.alternate(i))
+    });
+
+    let t = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(9),
+            Constraint::Fill(1),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(theme.selected)
+    .style(theme.fill(model.submissions.len()))
+    .highlight_spacing(HighlightSpacing::Always);
+    f.render_stateful_widget(t, table_area, &mut model.table_state);
+
+    let selected = model
+        .table_state
+        .selected()
+        .and_then(|i| model.submissions.get(i));
+    let response = selected.map(|s| s.response.clone()).unwrap_or_default();
+    let detail = vec![
+        Line::from(match selected.and_then(|s| s.note.as_ref()) {
+            Some(note) => format!("Note: {note}   Response: {response}"),
+            None => format!("Response: {response}"),
+        }),
+        Line::from(
+            model
+                .keymap
+                .hints(Context::History, &[Action::Up, Action::Down, Action::Close]),
+        )
+        .style(theme.selected),
+    ];
+    f.render_widget(
+        Paragraph::new(Text::from(detail))
+            .wrap(Wrap { trim: true })
+            .centered(),
+        detail_area,
+    );
+}
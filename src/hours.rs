@@ -1,15 +1,21 @@
-use crate::api::Api;
-use crate::model::Message;
-use crate::store::Store;
-use crate::ui::{centered_rect, BORDER_COLOR, POPUP_STYLE, SELECTED_COLOR};
+use crate::api::{self, Api};
+use crate::keys::{Action, Context, Keymap};
+use crate::model::{ActiveView, Message};
+use crate::mouse::Gesture;
+use crate::picker::{self, PickerEvent, PickerItem, ProjectPicker};
+use crate::store::{Filter, JournalEntry, Project, Store};
+use crate::task::{self, Task};
+use crate::theme::Theme;
+use crate::ui::centered_rect;
+use chrono::{Datelike, NaiveDate};
 use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use log::error;
 use ratatui::layout::{Constraint, Layout, Position, Rect};
-use ratatui::style::palette::tailwind;
-use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Padding, Paragraph, Wrap};
+use ratatui::widgets::{Block, Clear, Padding, Paragraph, Wrap};
 use ratatui::Frame;
+use std::sync::mpsc::Sender;
 
 use thiserror::Error;
 
@@ -23,16 +29,38 @@ pub enum HoursError {
 pub struct HoursModel {
     api: Api,
     store: Store,
-    project: String,
+    sender: Sender<Message>,
+    keymap: Keymap,
+    theme: Theme,
+    project: Option<Project>,
+    picker: ProjectPicker,
+    focus: Focus,
     input: String,
     character_index: usize,
     pub error_message: Option<String>,
+    /// The hours are being posted in the background
+    pub submitting: bool,
+    /// Where the form was last drawn, clicking outside of it closes the form
+    area: Rect,
+    /// The date in the user's time zone, on the clock of the model
+    pub today: NaiveDate,
+}
+
+/// The part of the form receiving key presses
+#[derive(Debug, Default, PartialEq, Eq)]
+enum Focus {
+    #[default]
+    Hours,
+    Project,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum HoursMessage {
-    Open(String),
+    /// Opens the form with the given project selected, usually the active project
+    Open(Option<String>),
     ValidationError(String),
+    /// The hours were posted, with a description for the notice, or failed with the error
+    Posted(Result<String, String>),
     Return,
 }
 
@@ -48,15 +76,85 @@ fn validate_hours(hours: f32) -> Result<()> {
     }
 }
 
+/// Posts the hours and records the outcome in the submission log, whether it succeeded or not.
+/// The source tells where the submission came from, the note why it was made.
+fn post(
+    api: &Api,
+    store: &Store,
+    hours: &crate::api::Hours,
+    source: &str,
+    note: Option<&str>,
+) -> Result<()> {
+    let receipt = api.post_hours(hours);
+    let (status_code, body) = match &receipt {
+        Ok(r) => (Some(r.status_code), r.body.clone()),
+        Err(err) => (None, err.to_string()),
+    };
+    store.insert_submission(hours, source, note, status_code, &body)?;
+
+    receipt?.ensure_success()?;
+    Ok(())
+}
+
+/// Validates and posts the hours, then stores them locally once accepted by the API.
+/// Every view logging hours goes through here.
+pub fn submit(api: &Api, store: &Store, hours: &crate::api::Hours, source: &str) -> Result<()> {
+    validate_hours(hours.hours)?;
+    let previous = store.get_hours(hours.project, &hours.date)?;
+    post(api, store, hours, source, None)?;
+    store.insert_hours(hours.project, &hours.hours, &hours.date)?;
+    store.insert_journal(hours.project, &hours.date, previous, hours.hours)
+}
+
+/// Restores the value replaced by the last submission, both remotely and locally.
+/// Returns the change which was undone, if there was any.
+pub fn undo(api: &Api, store: &Store) -> Result<Option<JournalEntry>> {
+    let Some(entry) = store.last_journal()? else {
+        return Ok(None);
+    };
+
+    let previous = entry.previous_hours.unwrap_or(0.0);
+    post(
+        api,
+        store,
+        &crate::api::Hours {
+            project: &entry.project_id,
+            date: entry.date,
+            hours: previous,
+        },
+        "undo",
+        Some(&format!("replaces {:.1}h", entry.hours)),
+    )?;
+    store.insert_hours(&entry.project_id, &previous, &entry.date)?;
+    store.mark_undone(entry.id)?;
+
+    Ok(Some(entry))
+}
+
 impl HoursModel {
-    pub fn new(api: Api, store: Store) -> Self {
+    pub fn new(
+        api: Api,
+        store: Store,
+        sender: Sender<Message>,
+        keymap: Keymap,
+        theme: Theme,
+        today: NaiveDate,
+    ) -> Self {
         HoursModel {
             api,
             store,
-            project: "".to_string(),
+            sender,
+            keymap,
+            theme,
+            project: None,
+            picker: ProjectPicker::default(),
+            focus: Focus::default(),
             input: String::new(),
             character_index: 0,
             error_message: None,
+            submitting: false,
+            area: Rect::default(),
+            today,
         }
     }
 
@@ -70,16 +168,62 @@ impl HoursModel {
         }
     }
 
+    /// Posts the hours in the background, so a slow or retried request leaves the view responsive.
+    /// The outcome is sent as [`HoursMessage::Posted`].
     fn add_hours(&mut self, hours: f32) -> Result<()> {
         validate_hours(hours)?;
-        self.api.post_hours(&crate::api::Hours {
-            project: &self.project,
-            date: chrono::Utc::now().date_naive(),
-            hours,
-        })?;
+        let project = self
+            .project
+            .clone()
+            .ok_or_else(|| eyre::eyre!("Select a project to log hours"))?;
 
-        self.store
-            .insert_hours(&self.project, &hours, &chrono::Utc::now().date_naive())
+        let (api, store, sender) = (self.api.clone(), self.store.clone(), self.sender.clone());
+        let today = self.today;
+        self.submitting = true;
+        task::spawn(Task::Submit, self.sender.clone(), move || {
+            let result = submit(
+                &api,
+                &store,
+                &crate::api::Hours {
+                    project: &project.project_id,
+                    date: today,
+                    hours,
+                },
+                "log hours",
+            );
+            let posted = match result {
+                Ok(()) => Ok(format!("Logged {hours:.1}h to {}", project.project_name)),
+                Err(err) => {
+                    error!("{err:?}");
+                    Err(api::describe(&err))
+                }
+            };
+            let _ = sender.send(Message::AddHours(HoursMessage::Posted(posted)));
+            Ok(())
+        });
+        Ok(())
+    }
+
+    /// Loads the projects into the selector and selects the given project
+    fn open(&mut self, project_id: Option<&str>) -> Result<()> {
+        let today = self.today;
+        let usage = self.store.project_usage(today.month(), today.year())?;
+        self.project = usage
+            .iter()
+            .map(|u| &u.project)
+            .find(|p| Some(p.project_id.as_str()) == project_id)
+            .cloned();
+        self.picker
+            .set_items(usage.into_iter().map(PickerItem::from).collect(), today);
+        if let Some(project) = &self.project {
+            self.picker.select(&Filter::Project(project.clone()));
+        }
+        self.focus = match self.project {
+            Some(_) => Focus::Hours,
+            None => Focus::Project,
+        };
+
+        Ok(())
     }
 
     fn enter_char(&mut self, new_char: char) {
@@ -134,24 +278,32 @@ impl HoursModel {
 }
 
 pub fn handle_key(key: KeyEvent, model: &mut HoursModel) -> Result<Option<Message>> {
-    match key.code {
-        KeyCode::Enter => {
-            if model.input.is_empty() {
-                return Ok(None);
+    let action = model.keymap.action(Context::Hours, &key);
+    if action == Some(Action::SwitchField) {
+        return run_action(model, Action::SwitchField);
+    }
+
+    if model.focus == Focus::Project {
+        match model.picker.handle_key(key, &model.keymap) {
+            Some(PickerEvent::Selected(Filter::Project(project))) => {
+                model.project = Some(project);
+                model.focus = Focus::Hours;
             }
-            // todo: handle error (send error message for hour input here)
-            match model.add_hours(model.input_to_float()?) {
-                Ok(_) => {
-                    model.input.clear();
-                    model.character_index = 0;
-                    model.error_message = None;
-                    Ok(Some(Message::RefreshCompleted))
-                }
-                Err(e) => Ok(Some(Message::AddHours(HoursMessage::ValidationError(
-                    e.to_string(),
-                )))),
+            Some(PickerEvent::Selected(Filter::Group { .. })) => {}
+            Some(PickerEvent::Cancelled) if model.project.is_some() => {
+                model.focus = Focus::Hours;
             }
+            Some(PickerEvent::Cancelled) => return Ok(Some(Message::Home)),
+            None => {}
         }
+        return Ok(None);
+    }
+
+    if let Some(action) = action {
+        return run_action(model, action);
+    }
+
+    match key.code {
         KeyCode::Char(c) => {
             model.enter_char(c);
             Ok(None)
@@ -165,31 +317,126 @@ pub fn handle_key(key: KeyEvent, model: &mut HoursModel) -> Result<Option<Messag
     }
 }
 
+/// Runs an action of the hours keys, also when picked in the command palette
+pub fn run_action(model: &mut HoursModel, action: Action) -> Result<Option<Message>> {
+    match action {
+        Action::SwitchField => {
+            model.focus = match model.focus {
+                Focus::Hours => Focus::Project,
+                Focus::Project => Focus::Hours,
+            };
+            Ok(None)
+        }
+        Action::Close => Ok(Some(Message::Home)),
+        Action::Submit => submit_input(model),
+        _ => Ok(None),
+    }
+}
+
+pub fn handle_mouse(
+    event: MouseEvent,
+    gesture: Gesture,
+    model: &mut HoursModel,
+) -> Option<Message> {
+    if model.focus == Focus::Project {
+        match model.picker.handle_mouse(event, gesture) {
+            Some(PickerEvent::Selected(Filter::Project(project))) => {
+                model.project = Some(project);
+                model.focus = Focus::Hours;
+            }
+            Some(PickerEvent::Cancelled) if model.project.is_some() => {
+                model.focus = Focus::Hours;
+            }
+            Some(PickerEvent::Cancelled) => return Some(Message::Home),
+            _ => {}
+        }
+        return None;
+    }
+
+    let outside = !model.area.contains(Position::new(event.column, event.row));
+    match gesture {
+        Gesture::Click | Gesture::DoubleClick if outside => Some(Message::Home),
+        _ => None,
+    }
+}
+
+fn submit_input(model: &mut HoursModel) -> Result<Option<Message>> {
+    if model.input.is_empty() || model.submitting {
+        return Ok(None);
+    }
+    // todo: handle error (send error message for hour input here)
+    let hours = model.input_to_float()?;
+    match model.add_hours(hours) {
+        Ok(()) => {
+            model.error_message = None;
+            Ok(None)
+        }
+        Err(e) => Ok(Some(Message::AddHours(HoursMessage::ValidationError(
+            crate::api::describe(&e),
+        )))),
+    }
+}
+
 pub fn update(model: &mut HoursModel, msg: HoursMessage) -> Result<Option<Message>> {
     match msg {
         HoursMessage::Open(project) => {
-            model.project = project;
+            model.open(project.as_deref())?;
             model.input.clear();
             model.character_index = 0;
-            Ok(Some(Message::View(crate::model::ActiveView::LogHours)))
+            Ok(Some(Message::View(ActiveView::LogHours)))
+        }
+        HoursMessage::Posted(posted) => {
+            model.submitting = false;
+            match posted {
+                Ok(description) => {
+                    model.input.clear();
+                    model.character_index = 0;
+                    Ok(Some(Message::Submitted(description)))
+                }
+                // the input is kept to try again
+                Err(e) => {
+                    model.error_message = Some(e);
+                    Ok(None)
+                }
+            }
         }
         HoursMessage::ValidationError(e) => {
             model.error_message = Some(e);
-            Ok(Some(Message::AddHours(HoursMessage::Open(
-                model.project.clone(),
-            ))))
+            model.input.clear();
+            model.character_index = 0;
+            Ok(Some(Message::View(ActiveView::LogHours)))
         }
         _ => Ok(None),
     }
 }
 
 pub fn render(f: &mut Frame, model: &mut HoursModel, area: Rect) {
+    let theme = model.theme;
+    if model.focus == Focus::Project {
+        let popup_area = centered_rect(40, 40, area);
+        f.render_widget(Clear, popup_area);
+        picker::render(
+            f,
+            &mut model.picker,
+            &theme,
+            popup_area,
+            "Log hours for project",
+            &format!(
+                "Select {}   Back {}",
+                model.keymap.label(Context::Picker, Action::Select),
+                model.keymap.label(Context::Picker, Action::Close)
+            ),
+        );
+        return;
+    }
+
     let popup_area = centered_rect(40, 20, area);
+    model.area = popup_area;
     let popup = Block::bordered()
         .padding(Padding::proportional(1))
         .title("Todays hours")
         .title_alignment(ratatui::layout::Alignment::Center)
-        .style(POPUP_STYLE);
+        .style(theme.popup);
 
     f.render_widget(&popup, popup_area);
     let inner = popup.inner(popup_area);
@@ -200,14 +447,25 @@ pub fn render(f: &mut Frame, model: &mut HoursModel, area: Rect) {
     ]);
     let [info_area, input_area, help_area] = vertical.areas(inner);
 
-    let text = vec![Line::from("Hours 0.0...24.0").centered()];
+    let project_name = model
+        .project
+        .as_ref()
+        .map_or("None", |p| p.project_name.as_str());
+    let text = vec![
+        Line::from(format!(
+            "Project: {project_name} {}",
+            model.keymap.label(Context::Hours, Action::SwitchField)
+        ))
+        .centered(),
+        Line::from("Hours 0.0...24.0").centered(),
+    ];
 
     let p = Paragraph::new(text);
     f.render_widget(p, info_area);
 
     let input = Paragraph::new(model.input.as_str())
         .block(Block::bordered().title("Hours"))
-        .style(BORDER_COLOR);
+        .style(theme.border);
 
     f.set_cursor_position(Position::new(
         input_area.x + model.character_index as u16 + 1,
@@ -215,21 +473,23 @@ pub fn render(f: &mut Frame, model: &mut HoursModel, area: Rect) {
     ));
     f.render_widget(input, input_area);
 
-    let note_span = Span::styled(
-        "Note!",
-        Style::default()
-            .add_modifier(Modifier::BOLD)
-            .fg(tailwind::RED.c200),
-    );
+    let note_span = Span::styled("Note!", theme.hint);
     let help_text = vec![
-        if let Some(error) = &model.error_message {
-            Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red)))
+        if model.submitting {
+            Line::from(Span::styled("Submitting…", theme.hint))
+        } else if let Some(error) = &model.error_message {
+            Line::from(Span::styled(error.clone(), theme.error))
         } else {
             Line::default()
         },
         Line::from(note_span).centered(),
-        Line::from("Overrides daily hours for active project"),
-        Line::from("<Enter> ").centered().style(SELECTED_COLOR),
+        Line::from("Overrides daily hours for selected project"),
+        Line::from(format!(
+            "{} ",
+            model.keymap.label(Context::Hours, Action::Submit)
+        ))
+        .centered()
+        .style(theme.selected),
     ];
 
     let help_paragaph = Paragraph::new(help_text)
@@ -240,7 +500,15 @@ pub fn render(f: &mut Frame, model: &mut HoursModel, area: Rect) {
 
 #[cfg(test)]
 mod tests {
-    use crate::hours::validate_hours;
+    use std::time::Duration;
+
+    use crossterm::event::KeyCode;
+
+    use crate::harness::Harness;
+    use crate::hours::{validate_hours, HoursMessage};
+    use crate::model::{ActiveView, Message};
+
+    const PROJECT_A: &str = "mBED9hi2XFs51XzeH0hD";
 
     #[test]
     fn test_validate_hours() {
@@ -250,4 +518,78 @@ mod tests {
         let valid_hours = validate_hours(24.0);
         assert!(valid_hours.is_ok());
     }
+
+    #[test]
+    fn test_render_hours() {
+        let mut harness = Harness::new();
+        harness.send(Message::AddHours(HoursMessage::Open(Some(
+            PROJECT_A.to_string(),
+        ))));
+        assert_eq!(harness.model.active_view, ActiveView::LogHours);
+        harness.type_str("7.5");
+        harness.assert_snapshot("hours", 100, 30);
+
+        for _ in 0..3 {
+            harness.key(KeyCode::Backspace);
+        }
+        harness.type_str("25").key(KeyCode::Enter);
+        harness.assert_snapshot("hours_invalid", 100, 30);
+        assert!(harness.api.requests("POST").is_empty());
+    }
+
+    #[test]
+    fn test_render_hours_picker() {
+        let mut harness = Harness::new();
+        harness.send(Message::AddHours(HoursMessage::Open(None)));
+        harness.assert_snapshot("hours_picker", 100, 30);
+    }
+
+    #[test]
+    fn test_submit_hours() {
+        let mut harness = Harness::new();
+        harness.api.delay(Duration::from_millis(100));
+        harness
+            .send(Message::AddHours(HoursMessage::Open(Some(
+                PROJECT_A.to_string(),
+            ))))
+            .type_str("6")
+            .key(KeyCode::Enter);
+
+        assert_eq!(
+            harness.model.active_view,
+            ActiveView::LogHours,
+            "posted in the background"
+        );
+        assert!(harness.model.add_hours_model.submitting);
+        harness.key(KeyCode::Enter);
+
+        harness.wait_for(|m| matches!(m, Message::AddHours(HoursMessage::Posted(_))));
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert_eq!(
+            harness.model.notice(),
+            Some("Logged 6.0h to Project A - undo <u>")
+        );
+        let posts = harness.api.requests("POST");
+        assert_eq!(posts.len(), 1, "submitting again waits for the first post");
+        assert_eq!(posts[0].path, "/entries-upsert-one");
+        assert!(posts[0].body.contains(PROJECT_A), "{}", posts[0].body);
+    }
+
+    #[test]
+    fn test_submit_hours_failure() {
+        let mut harness = Harness::new();
+        harness.api.fail("/entries-upsert-one");
+        harness
+            .send(Message::AddHours(HoursMessage::Open(Some(
+                PROJECT_A.to_string(),
+            ))))
+            .type_str("6")
+            .key(KeyCode::Enter);
+
+        harness.wait_for(|m| matches!(m, Message::AddHours(HoursMessage::Posted(_))));
+        assert_eq!(harness.model.active_view, ActiveView::LogHours);
+        assert!(!harness.model.add_hours_model.submitting);
+        let error = harness.model.add_hours_model.error_message.clone().unwrap();
+        assert!(error.contains("400"), "{error}");
+    }
 }
@@ -0,0 +1,673 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum KeyError {
+    #[error("Invalid key '{key}' for {action} in [keys.{context}]")]
+    InvalidKey {
+        context: Context,
+        action: Action,
+        key: String,
+    },
+    #[error("{action} is not available in [keys.{context}]")]
+    UnknownAction { context: Context, action: Action },
+    #[error("Key <{key}> is bound to both {first} and {second}")]
+    Conflict {
+        key: KeyBinding,
+        first: String,
+        second: String,
+    },
+    #[error("Key <{key}> is used for text input in [keys.{context}]")]
+    Reserved { context: Context, key: KeyBinding },
+}
+
+/// Where a key binding applies. Global keys apply to every view without text input.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Context {
+    Global,
+    Home,
+    Loading,
+    /// Lists filtered by typing, the project pickers and the command palette
+    Picker,
+    Project,
+    Hours,
+    Timesheet,
+    History,
+}
+
+impl Context {
+    fn name(self) -> &'static str {
+        match self {
+            Context::Global => "global",
+            Context::Home => "home",
+            Context::Loading => "loading",
+            Context::Picker => "picker",
+            Context::Project => "project",
+            Context::Hours => "hours",
+            Context::Timesheet => "timesheet",
+            Context::History => "history",
+        }
+    }
+
+    /// Keys the views of the context use for text input or the project picker
+    fn reserves(self, key: &KeyBinding) -> bool {
+        let plain_char = |c: &dyn Fn(char) -> bool| match key.code {
+            KeyCode::Char(ch) => key.modifiers.is_empty() && c(ch),
+            _ => false,
+        };
+        match self {
+            Context::Picker | Context::Project => {
+                plain_char(&|_| true) || key.code == KeyCode::Backspace
+            }
+            Context::Hours | Context::Timesheet => {
+                plain_char(&|c| c.is_ascii_digit() || c == '.') || key.code == KeyCode::Backspace
+            }
+            Context::Global | Context::Home | Context::Loading | Context::History => false,
+        }
+    }
+
+    /// The keys typed as input in the context, as shown in hints
+    pub fn input_label(self) -> Option<&'static str> {
+        match self {
+            Context::Picker | Context::Project => Some("<a-z>"),
+            Context::Hours | Context::Timesheet => Some("<0-9>"),
+            Context::Global | Context::Home | Context::Loading | Context::History => None,
+        }
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Home,
+    Quit,
+    LogHours,
+    Undo,
+    Help,
+    Palette,
+    ActiveProject,
+    Refresh,
+    Timesheet,
+    CopyLastWeek,
+    AcceptSuggestion,
+    AcceptAllSuggestions,
+    DismissSuggestion,
+    History,
+    SwitchProfile,
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    ClearFilter,
+    SwitchField,
+    Submit,
+    Close,
+    Clear,
+    AddProject,
+    PreviousWeek,
+    NextWeek,
+}
+
+impl Action {
+    fn name(self) -> &'static str {
+        match self {
+            Action::Home => "home",
+            Action::Quit => "quit",
+            Action::LogHours => "log_hours",
+            Action::Undo => "undo",
+            Action::Help => "help",
+            Action::Palette => "palette",
+            Action::ActiveProject => "active_project",
+            Action::Refresh => "refresh",
+            Action::Timesheet => "timesheet",
+            Action::CopyLastWeek => "copy_last_week",
+            Action::AcceptSuggestion => "accept_suggestion",
+            Action::AcceptAllSuggestions => "accept_all_suggestions",
+            Action::DismissSuggestion => "dismiss_suggestion",
+            Action::History => "history",
+            Action::SwitchProfile => "switch_profile",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Select => "select",
+            Action::ClearFilter => "clear_filter",
+            Action::SwitchField => "switch_field",
+            Action::Submit => "submit",
+            Action::Close => "close",
+            Action::Clear => "clear",
+            Action::AddProject => "add_project",
+            Action::PreviousWeek => "previous_week",
+            Action::NextWeek => "next_week",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A key with its modifiers, written as e.g. `q`, `Enter` or `Ctrl-x`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Shift is part of the character itself, so only Ctrl and Alt are compared
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        self.code == key.code
+            && key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT) == self.modifiers
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        loop {
+            let lower = rest.to_lowercase();
+            if rest.len() > 1 && lower.starts_with("ctrl-") {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[5..];
+            } else if rest.len() > 1 && lower.starts_with("alt-") {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(()),
+                },
+            },
+        };
+        Ok(KeyBinding { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::BackTab => f.write_str("BackTab"),
+            KeyCode::Backspace => f.write_str("Backspace"),
+            KeyCode::Delete => f.write_str("Delete"),
+            KeyCode::Up => f.write_str("Up"),
+            KeyCode::Down => f.write_str("Down"),
+            KeyCode::Left => f.write_str("Left"),
+            KeyCode::Right => f.write_str("Right"),
+            KeyCode::Home => f.write_str("Home"),
+            KeyCode::End => f.write_str("End"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// One key or a list of keys for an action in the `[keys]` section
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn as_slice(&self) -> &[String] {
+        match self {
+            Keys::One(key) => std::slice::from_ref(key),
+            Keys::Many(keys) => keys,
+        }
+    }
+}
+
+/// The `[keys]` section, e.g. `[keys.home] refresh = 'R'`
+pub type KeyConfig = BTreeMap<Context, BTreeMap<Action, Keys>>;
+
+/// Every action with its default keys and the description shown in the help
+const DEFAULTS: &[(Context, Action, &[&str], &str)] = &[
+    (Context::Global, Action::Home, &["H"], "Home screen"),
+    (Context::Global, Action::LogHours, &["l"], "Log hours"),
+    (Context::Global, Action::Undo, &["u"], "Undo last entry"),
+    (
+        Context::Global,
+        Action::Help,
+        &["?", "F1"],
+        "Keys of this view",
+    ),
+    (Context::Global, Action::Palette, &[":"], "Command palette"),
+    (Context::Global, Action::Quit, &["q"], "Quit application"),
+    (Context::Home, Action::Refresh, &["r"], "Refresh database"),
+    (Context::Home, Action::Timesheet, &["w"], "Week timesheet"),
+    (
+        Context::Home,
+        Action::CopyLastWeek,
+        &["C"],
+        "Copy last week",
+    ),
+    (
+        Context::Home,
+        Action::AcceptSuggestion,
+        &["a"],
+        "Accept suggestion",
+    ),
+    (
+        Context::Home,
+        Action::AcceptAllSuggestions,
+        &["A"],
+        "Accept all",
+    ),
+    (
+        Context::Home,
+        Action::DismissSuggestion,
+        &["d"],
+        "Dismiss suggestion",
+    ),
+    (
+        Context::Home,
+        Action::ActiveProject,
+        &["p"],
+        "Active project",
+    ),
+    (Context::Home, Action::History, &["S"], "Submission history"),
+    (
+        Context::Home,
+        Action::SwitchProfile,
+        &["P"],
+        "Switch profile",
+    ),
+    (Context::Home, Action::Up, &["k"], "Up"),
+    (Context::Home, Action::Down, &["j"], "Down"),
+    (Context::Home, Action::Select, &["Enter"], "Select"),
+    (Context::Loading, Action::Close, &["Esc"], "Cancel refresh"),
+    (Context::Picker, Action::Select, &["Enter"], "Select"),
+    (Context::Picker, Action::Up, &["Up", "Ctrl-p"], "Up"),
+    (Context::Picker, Action::Down, &["Down", "Ctrl-n"], "Down"),
+    (Context::Picker, Action::Close, &["Esc"], "Close"),
+    (Context::Project, Action::ClearFilter, &["Ctrl-x"], "Unset"),
+    (
+        Context::Hours,
+        Action::SwitchField,
+        &["Tab"],
+        "Switch field",
+    ),
+    (Context::Hours, Action::Submit, &["Enter"], "Log hours"),
+    (Context::Hours, Action::Close, &["Esc"], "Close"),
+    (Context::Timesheet, Action::Left, &["h", "Left"], "Left"),
+    (
+        Context::Timesheet,
+        Action::Right,
+        &["l", "Right", "Tab"],
+        "Right",
+    ),
+    (Context::Timesheet, Action::Up, &["k", "Up"], "Up"),
+    (Context::Timesheet, Action::Down, &["j", "Down"], "Down"),
+    (Context::Timesheet, Action::Clear, &["x", "Delete"], "Clear"),
+    (
+        Context::Timesheet,
+        Action::AddProject,
+        &["a"],
+        "Add project",
+    ),
+    (
+        Context::Timesheet,
+        Action::CopyLastWeek,
+        &["c"],
+        "Copy last week",
+    ),
+    (
+        Context::Timesheet,
+        Action::PreviousWeek,
+        &["["],
+        "Previous week",
+    ),
+    (Context::Timesheet, Action::NextWeek, &["]"], "Next week"),
+    (Context::Timesheet, Action::Submit, &["s"], "Submit"),
+    (Context::Timesheet, Action::Close, &["Esc"], "Close"),
+    (Context::History, Action::Up, &["k", "Up"], "Up"),
+    (Context::History, Action::Down, &["j", "Down"], "Down"),
+    (Context::History, Action::Close, &["Esc"], "Close"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Binding {
+    context: Context,
+    action: Action,
+    keys: Vec<KeyBinding>,
+    description: &'static str,
+}
+
+/// An action with its keys, as listed in the help and the command palette
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    pub context: Context,
+    pub action: Action,
+    /// The keys joined by `/`, e.g. `j/Down`
+    pub keys: String,
+    pub description: &'static str,
+}
+
+/// Maps keys to actions per context, built from the defaults and the `[keys]` section
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&KeyConfig::new()).expect("default key bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// Applies the configured keys on top of the defaults, rejecting keys which
+    /// are invalid, bound twice or needed for text input
+    pub fn new(config: &KeyConfig) -> Result<Self, KeyError> {
+        let parse = |context: Context, action: Action, keys: &[String]| {
+            keys.iter()
+                .map(|key| {
+                    key.parse().map_err(|()| KeyError::InvalidKey {
+                        context,
+                        action,
+                        key: key.clone(),
+                    })
+                })
+                .collect::<Result<Vec<KeyBinding>, KeyError>>()
+        };
+
+        let mut bindings = vec![];
+        for (context, action, keys, description) in DEFAULTS {
+            let keys = keys.iter().map(|k| (*k).to_string()).collect::<Vec<_>>();
+            let configured = config.get(context).and_then(|c| c.get(action));
+            bindings.push(Binding {
+                context: *context,
+                action: *action,
+                keys: parse(
+                    *context,
+                    *action,
+                    configured.map_or(&keys[..], Keys::as_slice),
+                )?,
+                description,
+            });
+        }
+
+        for (context, actions) in config {
+            for action in actions.keys() {
+                if !bindings
+                    .iter()
+                    .any(|b| b.context == *context && b.action == *action)
+                {
+                    return Err(KeyError::UnknownAction {
+                        context: *context,
+                        action: *action,
+                    });
+                }
+            }
+        }
+
+        let keymap = Keymap { bindings };
+        keymap.check()?;
+        Ok(keymap)
+    }
+
+    /// Home and loading keys are only checked after the global keys, and the project view
+    /// is a picker, so they must not overlap either
+    fn check(&self) -> Result<(), KeyError> {
+        let contexts = [
+            Context::Global,
+            Context::Home,
+            Context::Loading,
+            Context::Picker,
+            Context::Project,
+            Context::Hours,
+            Context::Timesheet,
+            Context::History,
+        ];
+        for context in contexts {
+            let mut seen: Vec<(KeyBinding, String)> = vec![];
+            for binding in self.bindings.iter().filter(|b| {
+                b.context == context
+                    || (matches!(context, Context::Home | Context::Loading)
+                        && b.context == Context::Global)
+                    || (context == Context::Project && b.context == Context::Picker)
+            }) {
+                for key in &binding.keys {
+                    if binding.context.reserves(key) {
+                        return Err(KeyError::Reserved {
+                            context: binding.context,
+                            key: *key,
+                        });
+                    }
+                    let name = format!("{}.{}", binding.context, binding.action);
+                    if let Some((_, first)) = seen.iter().find(|(k, _)| k == key) {
+                        return Err(KeyError::Conflict {
+                            key: *key,
+                            first: first.clone(),
+                            second: name,
+                        });
+                    }
+                    seen.push((*key, name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The action bound to the key in the context, if any
+    pub fn action(&self, context: Context, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|b| b.context == context && b.keys.iter().any(|k| k.matches(key)))
+            .map(|b| b.action)
+    }
+
+    /// The keys of an action as shown in hints, e.g. `<j/Down>`
+    pub fn label(&self, context: Context, action: Action) -> String {
+        let keys = self
+            .bindings
+            .iter()
+            .find(|b| b.context == context && b.action == action)
+            .map(|b| {
+                b.keys
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_default();
+        format!("<{keys}>")
+    }
+
+    /// Every action of every context, in the order of the help
+    pub fn commands(&self) -> Vec<Command> {
+        self.bindings
+            .iter()
+            .map(|b| Command {
+                context: b.context,
+                action: b.action,
+                keys: b
+                    .keys
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                description: b.description,
+            })
+            .collect()
+    }
+
+    /// Keys and descriptions of the actions in the contexts, in the order of the help
+    pub fn help(&self, contexts: &[Context]) -> Vec<(String, &'static str)> {
+        self.commands()
+            .into_iter()
+            .filter(|c| contexts.contains(&c.context) && !c.keys.is_empty())
+            .map(|c| (c.keys, c.description))
+            .collect()
+    }
+
+    /// `<keys> description` for the actions, for hint lines at the bottom of a view
+    pub fn hints(&self, context: Context, actions: &[Action]) -> String {
+        actions
+            .iter()
+            .filter_map(|action| {
+                self.bindings
+                    .iter()
+                    .find(|b| b.context == context && b.action == *action)
+            })
+            .map(|b| format!("{} {}", b.description, self.label(context, b.action)))
+            .collect::<Vec<_>>()
+            .join("   ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::keys::{Action, Context, KeyBinding, KeyConfig, KeyError, Keymap};
+
+    fn config(toml: &str) -> KeyConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let key: KeyBinding = "Ctrl-x".parse().unwrap();
+        assert_eq!(key.code, KeyCode::Char('x'));
+        assert_eq!(key.modifiers, KeyModifiers::CONTROL);
+        assert_eq!(key.to_string(), "Ctrl-x");
+
+        assert_eq!("-".parse::<KeyBinding>().unwrap().code, KeyCode::Char('-'));
+        assert_eq!("enter".parse::<KeyBinding>().unwrap().code, KeyCode::Enter);
+        assert_eq!("F5".parse::<KeyBinding>().unwrap().code, KeyCode::F(5));
+        assert!("Hyper-x".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn test_default_keymap() {
+        let keymap = Keymap::default();
+        let shift_a = KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT);
+        assert_eq!(
+            keymap.action(Context::Home, &shift_a),
+            Some(Action::AcceptAllSuggestions)
+        );
+        let ctrl_x = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(
+            keymap.action(Context::Project, &ctrl_x),
+            Some(Action::ClearFilter)
+        );
+        assert_eq!(keymap.action(Context::Timesheet, &ctrl_x), None);
+        assert_eq!(keymap.label(Context::History, Action::Down), "<j/Down>");
+        let ctrl_n = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(Context::Picker, &ctrl_n), Some(Action::Down));
+        let question = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT);
+        assert_eq!(
+            keymap.action(Context::Global, &question),
+            Some(Action::Help)
+        );
+        assert!(keymap
+            .commands()
+            .iter()
+            .any(|c| c.context == Context::Timesheet && c.action == Action::NextWeek));
+    }
+
+    #[test]
+    fn test_configured_keymap() {
+        let keymap = Keymap::new(&config(
+            r#"
+            [home]
+            refresh = 'R'
+            down = ['j', 'Down']
+            "#,
+        ))
+        .unwrap();
+        let r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(Context::Home, &r), None);
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(keymap.action(Context::Home, &down), Some(Action::Down));
+    }
+
+    #[test]
+    fn test_invalid_keymap() {
+        assert!(matches!(
+            Keymap::new(&config("[home]\nrefresh = 'q'")),
+            Err(KeyError::Conflict { .. })
+        ));
+        assert!(matches!(
+            Keymap::new(&config("[loading]\nclose = 'q'")),
+            Err(KeyError::Conflict { .. })
+        ));
+        assert!(matches!(
+            Keymap::new(&config("[timesheet]\nsubmit = 'a'")),
+            Err(KeyError::Conflict { .. })
+        ));
+        assert!(matches!(
+            Keymap::new(&config("[project]\nclear_filter = 'x'")),
+            Err(KeyError::Reserved { .. })
+        ));
+        assert!(matches!(
+            Keymap::new(&config("[project]\nclear_filter = 'Ctrl-n'")),
+            Err(KeyError::Conflict { .. })
+        ));
+        assert!(matches!(
+            Keymap::new(&config("[home]\nsubmit = 's'")),
+            Err(KeyError::UnknownAction { .. })
+        ));
+        assert!(matches!(
+            Keymap::new(&config("[home]\nrefresh = 'Hyper-r'")),
+            Err(KeyError::InvalidKey { .. })
+        ));
+    }
+}
@@ -1,10 +1,27 @@
+pub mod activity;
 pub mod api;
 pub mod config;
+pub mod events;
+pub mod fuzzy;
+pub mod keys;
 pub mod model;
+pub mod mouse;
+pub mod recurring;
 pub mod store;
+pub mod sync;
+pub mod task;
+pub mod theme;
 pub mod tui;
 pub mod ui;
+pub mod update;
+
+#[cfg(test)]
+mod harness;
 
 // contains the widgets
+pub mod history;
 pub mod hours;
+pub mod palette;
+pub mod picker;
 pub mod project;
+pub mod timesheet;
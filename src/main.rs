@@ -1,7 +1,7 @@
 #![warn(clippy::all, clippy::pedantic)]
 use std::{
     sync::mpsc::{self, Receiver, Sender},
-    time::{Duration, SystemTime},
+    time::SystemTime,
 };
 
 use chrono::Utc;
@@ -9,16 +9,15 @@ use clap::{Parser, Subcommand};
 use color_eyre::{Report, Result, Section};
 use eyre::eyre;
 use log::error;
-use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
-    prelude::Backend,
-    Terminal,
-};
+use ratatui::{prelude::Backend, Terminal};
 use timet_tui::{
-    api, config, hours,
-    model::{ActiveView, Message, Model, RunningState},
-    project, store, tui,
+    api, config, events, hours,
+    model::{Message, RunningState},
+    store,
+    theme::Theme,
+    tui,
     ui::view,
+    update::{apply, load_model},
 };
 
 #[derive(Parser)]
@@ -26,12 +25,32 @@ use timet_tui::{
 struct TimetTui {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Profile from the configuration to use
+    #[arg(short, long, global = true)]
+    profile: Option<String>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Create or update the API key in the secure store
     ApiKey,
+    /// Create the configuration and store the API key
+    Init,
+    /// Restore the value replaced by the last logged or changed entry
+    Undo,
+    /// Print the most recent submissions from the local submission log
+    History {
+        /// Number of submissions to print
+        #[arg(short, long, default_value_t = 50)]
+        limit: usize,
+    },
+}
+
+/// What runs in the terminal once it is initialised, the other commands only print
+enum Screen {
+    ApiKey,
+    Init,
+    App,
 }
 
 fn main() -> Result<(), Report> {
@@ -60,172 +79,143 @@ fn main() -> Result<(), Report> {
                 config::VERSION,
                 config::COMMIT,
                 message
-            ))
+            ));
         })
         .level(log::LevelFilter::Info)
         .chain(fern::log_file(log_location)?)
         .apply()?;
 
+    let screen = match &cli.command {
+        Some(Commands::Undo) => return undo(cli.profile.as_deref()),
+        Some(Commands::History { limit }) => return history(cli.profile.as_deref(), *limit),
+        Some(Commands::ApiKey) => Screen::ApiKey,
+        Some(Commands::Init) => Screen::Init,
+        None => Screen::App,
+    };
+
     let mut terminal = tui::init_terminal()?;
 
-    let result = match &cli.command {
-        Some(Commands::ApiKey) => match config::setup::enter_api_key(&mut terminal) {
-            Ok(api_key) => config::set_api_key(&api_key),
-            Err(err) => Err(err),
-        },
-        None => match app(&mut terminal) {
-            Ok(_) => Ok(()),
+    let mut cancelled = false;
+    let result = match screen {
+        Screen::ApiKey => config::Config::load(cli.profile.as_deref()).and_then(|cfg| {
+            match config::setup::enter_api_key(&mut terminal, &cfg.styles, &cfg.api.endpoint)? {
+                Some(api_key) => config::set_api_key(&cfg.keyring_entry(), &api_key),
+                None => Ok(()),
+            }
+        }),
+        Screen::Init => {
+            init(&mut terminal, cli.profile.as_deref()).map(|written| cancelled = !written)
+        }
+        Screen::App => match first_run(&mut terminal, cli.profile.as_deref()).and_then(|ready| {
+            cancelled = !ready;
+            if ready {
+                app(&mut terminal, cli.profile.as_deref())
+            } else {
+                Ok(())
+            }
+        }) {
+            Ok(()) => Ok(()),
             Err(err) => {
                 error!("{:?}", err.root_cause());
                 Err(eyre!(err)
-                    .with_suggestion(move || format!("Check logs for more info: {}", log_location)))
+                    .with_suggestion(move || format!("Check logs for more info: {log_location}")))
             }
         },
     };
 
     tui::restore_terminal()?;
 
+    if cancelled {
+        println!("Setup cancelled");
+    }
     result
 }
-fn app(terminal: &mut Terminal<impl Backend>) -> Result<()> {
-    let config = config::Config::new()?;
-
+fn undo(profile: Option<&str>) -> Result<()> {
+    let config = config::Config::new(profile)?;
     let store = store::Store::new(&config)?;
     let remote_api = api::Api::new(&config);
-    let (sender, receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
-    let mut model = Model::new(sender.clone(), remote_api, store, config)?;
 
-    while model.running_state != RunningState::Done {
-        // Render the current view
-        terminal.draw(|f| view(&mut model, f))?;
+    match hours::undo(&remote_api, &store)? {
+        Some(entry) => println!(
+            "Restored {} on {} from {:.1}h to {:.1}h",
+            entry.project_name,
+            entry.date,
+            entry.hours,
+            entry.previous_hours.unwrap_or(0.0)
+        ),
+        None => println!("Nothing to undo"),
+    }
+    Ok(())
+}
 
-        // Handle events and map to a Message
-        let mut current_msg = handle_event(&mut model, &receiver)?;
+fn history(profile: Option<&str>, limit: usize) -> Result<()> {
+    let config = config::Config::load(profile)?;
+    let store = store::Store::new(&config)?;
 
-        // Process updates as long as they return a non-None message
-        while current_msg.is_some() {
-            current_msg = update(&mut model, current_msg.unwrap())?;
-        }
+    for s in store.submissions(limit)? {
+        println!(
+            "{}\t{}\t{}\t{:.1}\t{}\t{}\t{}\t{}",
+            s.submitted.to_rfc3339(),
+            s.date,
+            s.project_name,
+            s.hours,
+            s.status_code.map_or("-".to_string(), |c| c.to_string()),
+            s.source,
+            s.note.as_deref().unwrap_or("-"),
+            s.response.replace(['\n', '\t'], " "),
+        );
     }
-
     Ok(())
 }
 
-/// Convert Event to Message
-fn handle_event(model: &mut Model, receiver: &Receiver<Message>) -> Result<Option<Message>> {
-    match receiver.try_recv() {
-        Ok(m) => {
-            return Ok(Some(m));
-        }
-        Err(_) => {
-            // Simply go on
-        }
+/// Asks for the configuration with the `init` wizard, then writes it and stores the key
+/// in the keyring entry of the profile. Returns whether it was written, the wizard can be cancelled.
+fn init(terminal: &mut Terminal<impl Backend>, profile: Option<&str>) -> Result<bool> {
+    let location = config::locate_config()?;
+    if config::config_exists() {
+        return Err(eyre!("{location}/config.toml already exists")
+            .suggestion("Edit the file directly, or run `timet-tui api-key` to replace the key"));
     }
 
-    if event::poll(Duration::from_millis(250))? {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == event::KeyEventKind::Press {
-                return handle_key(key, model);
-            }
-        }
-    }
-    Ok(None)
+    let Some(init) = config::setup::init(terminal, &Theme::default(), &location)? else {
+        return Ok(false);
+    };
+    config::write_config(&location, &init, profile)?;
+    let cfg = config::Config::load(profile)?;
+    config::set_api_key(&cfg.keyring_entry(), &init.api_key).suggestion(
+        "The configuration was written, set key_command or key_file if there is no keyring",
+    )?;
+    Ok(true)
 }
 
-fn handle_key(key: event::KeyEvent, model: &mut Model) -> Result<Option<Message>> {
-    match key.code {
-        // Global keys
-        KeyCode::Char('H') => Ok(Some(Message::Home)),
-        KeyCode::Char('q') => Ok(Some(Message::Quit)),
-        KeyCode::Char('l') => match &model.active_project {
-            Some(project) => Ok(Some(Message::AddHours(hours::HoursMessage::Open(
-                project.project_id.clone(),
-            )))),
-            _ => {
-                model.active_error_msg =
-                    Some("An active project must be set to log hours".to_string());
-                Ok(None)
-            }
-        },
-
-        _ => {
-            match model.active_view {
-                ActiveView::LogHours => hours::handle_key(key, &mut model.add_hours_model),
-                ActiveView::Hours => project::handle_key(key, &mut model.register_model),
-                // this breaks detailMonth because it has no keys attached
-                ActiveView::Home => match key.code {
-                    KeyCode::Char('H') => Ok(Some(Message::Home)),
-                    KeyCode::Char('p') => Ok(Some(Message::Hours(project::ProjectMessage::Open))),
-                    KeyCode::Char('r') => Ok(Some(Message::RefreshStarted)),
-                    KeyCode::Char('j') => {
-                        model.next_row()?;
-                        Ok(None)
-                    }
-                    KeyCode::Char('k') => {
-                        model.previous_row()?;
-                        Ok(None)
-                    }
-                    KeyCode::Enter => {
-                        model.set_active_month()?;
-                        Ok(Some(Message::DetailMonth))
-                    }
-                    _ => Ok(None),
-                },
-                _ => Ok(None),
-            }
-        }
+/// Starts the `init` wizard when there is no configuration yet, returning whether the app
+/// can start
+fn first_run(terminal: &mut Terminal<impl Backend>, profile: Option<&str>) -> Result<bool> {
+    if config::config_exists() {
+        return Ok(true);
     }
+    init(terminal, profile)
 }
 
-fn update(model: &mut Model, msg: Message) -> Result<Option<Message>> {
-    match msg {
-        Message::View(view) => {
-            model.active_view = view;
-            Ok(None)
-        }
-        Message::Home => {
-            model.active_error_msg = None;
-            Ok(Some(Message::View(ActiveView::Home)))
-        }
-        Message::DetailMonth => {
-            model.set_active_month()?;
-            Ok(Some(Message::View(ActiveView::Month)))
-        }
-        Message::RefreshStarted => {
-            model.refresh();
-            Ok(Some(Message::View(ActiveView::Loading)))
-        }
-        Message::RefreshProgressing(month) => {
-            model.update_month = month;
-            Ok(Some(Message::View(ActiveView::Loading)))
-        }
-        Message::RefreshCompleted => {
-            model.overview = model.store.get_yearly_overview(model.active_year)?;
-            Ok(Some(Message::View(ActiveView::Home)))
-        }
-        Message::RefreshFailed(msg) => {
-            model.active_error_msg = Some(format!("API error: {}", msg.as_str()));
-            Ok(None)
+fn app(terminal: &mut Terminal<impl Backend>, profile: Option<&str>) -> Result<()> {
+    let (sender, receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+    let mut model = load_model(sender.clone(), profile)?;
+    events::spawn_input(sender.clone());
+    events::spawn_ticks(sender, events::TICK_RATE);
+
+    let mut changed = true;
+    while model.running_state != RunningState::Done {
+        if changed {
+            terminal.draw(|f| view(&mut model, f))?;
         }
-        Message::Hours(m) => project::update(&mut model.register_model, m),
-        Message::Quit => {
-            model.running_state = RunningState::Done;
-            Ok(None)
+
+        // Wait for input, a tick or a background task, then apply everything pending at once
+        let msg = receiver.recv()?;
+        changed = apply(&mut model, msg)?;
+        while let Ok(msg) = receiver.try_recv() {
+            changed |= apply(&mut model, msg)?;
         }
-        Message::ActiveProject(project) => match project {
-            Some(p) => {
-                model.store.insert_active_project(&p.project_id)?;
-                model.active_project = Some(p);
-                model.overview = model.store.get_yearly_overview(model.active_year)?;
-                Ok(Some(Message::View(ActiveView::Home)))
-            }
-            None => {
-                model.store.delete_active_project()?;
-                model.active_project = None;
-                model.overview = model.store.get_yearly_overview(model.active_year)?;
-                Ok(Some(Message::View(ActiveView::Home)))
-            }
-        },
-        Message::AddHours(hmsg) => hours::update(&mut model.add_hours_model, hmsg),
     }
+
+    Ok(())
 }
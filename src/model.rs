@@ -1,17 +1,36 @@
+use std::collections::HashMap;
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
-use chrono::Datelike;
-use color_eyre::Result;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use color_eyre::{eyre::eyre, Result};
+use crossterm::event::Event;
 use log::error;
 use ratatui::widgets::TableState;
 
-use crate::api::Api;
-use crate::config::Config;
-use crate::hours::{HoursMessage, HoursModel};
+use crate::activity::{self, DayActivity};
+use crate::api::{self, Api};
+use crate::config::{Config, Git};
+use crate::history::{HistoryMessage, HistoryModel};
+use crate::hours::{self, HoursMessage, HoursModel};
+use crate::keys::{Action, Context, Keymap};
+use crate::mouse::{Clicks, TableArea};
+use crate::palette::{PaletteMessage, PaletteModel};
 use crate::project::{ProjectMessage, ProjectModel};
-use crate::store::{Month, Project, Store, Year};
+use crate::recurring::{self, Suggestion};
+use crate::store::{Filter, JournalEntry, Month, Project, Store, Year};
+use crate::sync::{MonthStatus, Refresh, Refreshing};
+use crate::task::{self, Task, TaskFailure};
+use crate::theme::Theme;
+use crate::timesheet::{TimesheetMessage, TimesheetModel};
+
+/// How long a notice stays visible in the footer
+const NOTICE_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// Frames of the spinner shown in the header while syncing in the background
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
 #[derive(Debug)]
 pub struct Model {
@@ -22,28 +41,80 @@ pub struct Model {
     pub counter: i32,
     pub register_model: ProjectModel,
     pub add_hours_model: HoursModel,
+    pub timesheet_model: TimesheetModel,
+    pub history_model: HistoryModel,
+    pub palette_model: PaletteModel,
+    pub keymap: Keymap,
+    pub theme: Theme,
     pub active_error_msg: Option<String>,
+    /// The keys of the active view are shown on top of it
+    pub show_help: bool,
+    notice: Option<(String, Instant)>,
     pub running_state: RunningState,
-    pub now: chrono::DateTime<chrono::Utc>,
+    pub now: DateTime<Utc>,
     pub active_view: ActiveView,
+    /// The view shown again once a refresh ends
+    pub previous_view: ActiveView,
     pub projects: Vec<Project>,
-    pub active_project: Option<Project>,
+    pub active_filter: Option<Filter>,
     pub active_year: i32,
     pub active_month: u32,
-    pub update_month: u32,
+    /// The refresh in progress, if any
+    pub refreshing: Option<Refreshing>,
+    /// When the store was last synced with the API
+    pub last_synced: Option<DateTime<Utc>>,
+    /// When the next background refresh starts, if `refresh_interval` is set
+    next_refresh: Option<DateTime<Utc>>,
+    spinner: usize,
     pub overview: Vec<Year>,
     pub overview_month: Vec<Month>,
+    pub month_activity: Vec<DayActivity>,
+    pub suggestions: Vec<Suggestion>,
+    /// Suggestions or an undo are being posted in the background
+    pub submitting: bool,
+    activity_cache: HashMap<(i32, u32), Activity>,
     pub table_state: TableState,
+    /// Selection in the table of the active month
+    pub month_table_state: TableState,
+    /// Where the yearly and monthly tables were last drawn, for the mouse
+    pub year_area: TableArea,
+    pub month_area: TableArea,
+    pub clicks: Clicks,
 }
 
 impl Model {
     pub fn new(sender: Sender<Message>, api: Api, store: Store, config: Config) -> Result<Self> {
-        let now = chrono::Utc::now();
-        let overview = store.get_yearly_overview(now.year())?;
-        let active_project = store.default_project()?;
-        let rs = ProjectModel::new(store.clone())?;
-        let hr = HoursModel::new(api.clone(), store.clone());
-        Ok(Model {
+        let now = Utc::now();
+        let today = local_date(now);
+        let overview = store.get_yearly_overview(today.year())?;
+        let active_filter = load_filter(&store, &config)?;
+        let keymap = config.keymap.clone();
+        let theme = config.styles;
+        let rs = ProjectModel::new(
+            store.clone(),
+            config.groups.clone(),
+            keymap.clone(),
+            theme,
+            today,
+        )?;
+        let hr = HoursModel::new(
+            api.clone(),
+            store.clone(),
+            sender.clone(),
+            keymap.clone(),
+            theme,
+            today,
+        );
+        let ts = TimesheetModel::new(
+            api.clone(),
+            store.clone(),
+            sender.clone(),
+            config.holidays.clone(),
+            keymap.clone(),
+            theme,
+            today,
+        );
+        let mut model = Model {
             config,
             sender,
             api: Arc::new(api.clone()),
@@ -51,71 +122,342 @@ impl Model {
             counter: 0,
             register_model: rs,
             add_hours_model: hr,
+            timesheet_model: ts,
+            history_model: HistoryModel::new(store.clone(), keymap.clone(), theme),
+            palette_model: PaletteModel::new(&keymap, theme),
+            keymap,
+            theme,
             active_error_msg: None,
+            show_help: false,
+            notice: None,
             running_state: RunningState::Running,
             now,
             active_view: ActiveView::Home,
+            previous_view: ActiveView::Home,
             projects: vec![],
-            active_project,
-            active_year: now.year(),
+            active_filter,
+            active_year: today.year(),
             active_month: 0,
-            update_month: 0,
+            refreshing: None,
+            last_synced: None,
+            next_refresh: None,
+            spinner: 0,
             overview,
             overview_month: vec![],
+            month_activity: vec![],
+            suggestions: vec![],
+            submitting: false,
+            activity_cache: HashMap::new(),
             table_state: TableState::default().with_selected(0),
-        })
+            month_table_state: TableState::default(),
+            year_area: TableArea::default(),
+            month_area: TableArea::default(),
+            clicks: Clicks::default(),
+        };
+        model.set_suggestions()?;
+        model.schedule_refresh();
+
+        Ok(model)
     }
 
-    pub fn refresh(&self) {
-        let api = self.api.clone();
-        let store = self.store.clone();
-        let sender = self.sender.clone();
-        let now = self.now;
-        thread::spawn(move || {
-            match api.get_year(&sender, now, now.year() as u32) {
-                Ok(items) => {
-                    store.entry_truncate().unwrap();
-                    store.insert(items).unwrap();
-                    sender.send(Message::RefreshCompleted).unwrap();
-                }
-                Err(err) => {
-                    error!("{}", err);
-                    sender
-                        .send(Message::RefreshFailed(err.root_cause().to_string()))
-                        .unwrap()
+    /// The date in the user's time zone, which decides when the day changes
+    pub fn today(&self) -> NaiveDate {
+        local_date(self.now)
+    }
+
+    /// Moves the clock of the model and its views, returning the previous time
+    pub fn set_now(&mut self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = local_date(now);
+        self.register_model.today = today;
+        self.add_hours_model.today = today;
+        self.timesheet_model.today = today;
+        std::mem::replace(&mut self.now, now)
+    }
+
+    /// Collects the recurring entries of the current week which have not been logged yet
+    pub fn set_suggestions(&mut self) -> Result<()> {
+        let today = self.today();
+        let from = crate::timesheet::week_start(today);
+        let entries = self.store.get_entries(from, today)?;
+        let dismissed = self.store.get_dismissed(from, today)?;
+
+        self.suggestions =
+            recurring::pending(&self.config.recurring, &entries, &dismissed, from, today)?;
+        for suggestion in &mut self.suggestions {
+            if let Ok(name) = self.store.get_project_name(&suggestion.project_id) {
+                suggestion.project_name = name;
+            }
+        }
+        Ok(())
+    }
+
+    /// Logs the oldest suggestion, or all of them, through the normal post hours path.
+    /// They are posted in the background, the outcome is sent as [`Message::SuggestionsAccepted`].
+    pub fn accept_suggestions(&mut self, all: bool) {
+        if self.submitting {
+            self.notify("Still submitting, try again in a moment".to_string());
+            return;
+        }
+        let count = if all { self.suggestions.len() } else { 1 };
+        let suggestions: Vec<Suggestion> = self.suggestions.iter().take(count).cloned().collect();
+        if suggestions.is_empty() {
+            return;
+        }
+
+        self.submitting = true;
+        let (api, store, sender) = (self.api.clone(), self.store.clone(), self.sender.clone());
+        task::spawn(Task::Submit, self.sender.clone(), move || {
+            let mut accepted = 0;
+            let mut error = None;
+            for suggestion in &suggestions {
+                let result = hours::submit(
+                    &api,
+                    &store,
+                    &crate::api::Hours {
+                        project: &suggestion.project_id,
+                        date: suggestion.date,
+                        hours: suggestion.hours,
+                    },
+                    "suggestion",
+                );
+                if let Err(err) = result {
+                    error!("{:?}", err);
+                    error = Some(api::describe(&err));
+                    break;
                 }
-            };
+                accepted += 1;
+            }
+            let _ = sender.send(Message::SuggestionsAccepted(accepted, error));
+            Ok(())
         });
     }
 
-    pub fn next_row(&mut self) -> Result<()> {
-        let i = match self.table_state.selected() {
-            Some(i) => {
-                if i == self.overview.len() {
-                    i
-                } else {
-                    i + 1
-                }
+    /// Shows how accepting suggestions went and reloads what they changed
+    pub fn suggestions_accepted(&mut self, accepted: usize, error: Option<String>) -> Result<()> {
+        self.submitting = false;
+        if let Some(err) = error {
+            self.active_error_msg = Some(format!("Suggestion: {err}"));
+        }
+        if accepted > 0 {
+            self.notify(format!("Accepted {accepted} suggestion(s)"));
+        }
+        self.overview = self.store.get_yearly_overview(self.active_year)?;
+        self.set_suggestions()
+    }
+
+    /// Shows a short-lived message in the footer
+    pub fn notify(&mut self, msg: String) {
+        self.notice = Some((msg, Instant::now()));
+    }
+
+    /// The current notice, unless it has expired
+    pub fn notice(&self) -> Option<&str> {
+        self.notice
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < NOTICE_TIMEOUT)
+            .map(|(msg, _)| msg.as_str())
+    }
+
+    /// Moves the clock, reloading what depends on the date once the day has changed.
+    /// Returns whether anything shown has changed, so the view is only drawn when needed.
+    pub fn tick(&mut self, now: DateTime<Utc>) -> Result<bool> {
+        let status = self.sync_status();
+        let previous = self.set_now(now);
+        let expired = self.notice.is_some() && self.notice().is_none();
+        if expired {
+            self.notice = None;
+        }
+        if self.refresh_kind() == Some(Refresh::Background) {
+            self.spinner += 1;
+        }
+        if let Some(failure) = self.refreshing.as_mut().and_then(Refreshing::watch) {
+            // handled like the failures the task reports itself
+            let _ = self.sender.send(Message::TaskFailed(failure));
+        }
+        if self.refreshing.is_none() && self.next_refresh.is_some_and(|at| now >= at) {
+            self.start_refresh(Refresh::Background);
+        }
+        let changed = expired || status != self.sync_status();
+        let today = self.today();
+        if local_date(previous) == today {
+            return Ok(changed);
+        }
+
+        if self.active_year == local_date(previous).year() {
+            self.active_year = today.year();
+        }
+        self.overview = self.store.get_yearly_overview(self.active_year)?;
+        self.set_suggestions()?;
+        Ok(true)
+    }
+
+    /// Restores the value replaced by the last submission in the background,
+    /// the outcome is sent as [`Message::Undone`]
+    pub fn undo(&mut self) {
+        if self.submitting {
+            self.notify("Still submitting, try again in a moment".to_string());
+            return;
+        }
+        self.submitting = true;
+        let (api, store, sender) = (self.api.clone(), self.store.clone(), self.sender.clone());
+        task::spawn(Task::Submit, self.sender.clone(), move || {
+            let undone = hours::undo(&api, &store).map_err(|err| {
+                error!("{:?}", err);
+                api::describe(&err)
+            });
+            let _ = sender.send(Message::Undone(undone));
+            Ok(())
+        });
+    }
+
+    /// Shows what was undone and reloads what it changed
+    pub fn undone(
+        &mut self,
+        undone: std::result::Result<Option<JournalEntry>, String>,
+    ) -> Result<()> {
+        self.submitting = false;
+        match undone {
+            Ok(Some(entry)) => self.notify(format!(
+                "Restored {} on {} to {:.1}h",
+                entry.project_name,
+                entry.date,
+                entry.previous_hours.unwrap_or(0.0)
+            )),
+            Ok(None) => self.notify("Nothing to undo".to_string()),
+            Err(err) => self.active_error_msg = Some(format!("Undo: {err}")),
+        }
+        self.overview = self.store.get_yearly_overview(self.active_year)?;
+        self.set_suggestions()
+    }
+
+    /// Hides the oldest suggestion
+    pub fn dismiss_suggestion(&mut self) -> Result<()> {
+        if let Some(suggestion) = self.suggestions.first() {
+            self.store
+                .insert_dismissed(&suggestion.date, &suggestion.project_id)?;
+        }
+        self.set_suggestions()
+    }
+
+    /// Starts syncing with the API, unless a refresh is already running.
+    /// A manual refresh takes over a background refresh, to show its progress.
+    pub fn start_refresh(&mut self, refresh: Refresh) {
+        match &mut self.refreshing {
+            None => {
+                self.refreshing = Some(Refreshing::start(
+                    refresh,
+                    self.api.clone(),
+                    self.store.clone(),
+                    self.sender.clone(),
+                    self.today().year(),
+                    self.today().month(),
+                ));
             }
+            Some(running) if refresh == Refresh::Manual => running.refresh = refresh,
+            Some(_) => {}
+        }
+    }
 
+    /// How the running refresh was started, if there is one
+    pub fn refresh_kind(&self) -> Option<Refresh> {
+        self.refreshing.as_ref().map(|r| r.refresh)
+    }
+
+    /// Whether the message of a refresh belongs to the running refresh,
+    /// rather than to one which was cancelled or has ended
+    pub fn is_refreshing(&self, generation: u64) -> bool {
+        self.refreshing
+            .as_ref()
+            .is_some_and(|r| r.generation == generation)
+    }
+
+    /// Records the outcome of a month of the running refresh
+    pub fn refresh_month(&mut self, generation: u64, month: u32, error: Option<String>) {
+        if !self.is_refreshing(generation) {
+            return;
+        }
+        if let Some(refreshing) = &mut self.refreshing {
+            refreshing.set(
+                month,
+                error.map_or(MonthStatus::Synced, MonthStatus::Failed),
+            );
+        }
+    }
+
+    /// Ends the running refresh and schedules the next one, returning it
+    pub fn finish_refresh(&mut self, synced: bool) -> Option<Refreshing> {
+        if synced {
+            self.last_synced = Some(self.now);
+        }
+        self.schedule_refresh();
+        self.refreshing.take()
+    }
+
+    /// Stops the running refresh, keeping the months which were already stored
+    pub fn cancel_refresh(&mut self) -> Option<Refreshing> {
+        let refreshing = self.finish_refresh(false)?;
+        refreshing.cancel();
+        Some(refreshing)
+    }
+
+    /// Plans the next background refresh one `refresh_interval` from now
+    pub fn schedule_refresh(&mut self) {
+        self.next_refresh = self
+            .config
+            .refresh_interval
+            .and_then(|interval| chrono::Duration::from_std(interval).ok())
+            .map(|interval| self.now + interval);
+    }
+
+    /// A spinner while syncing in the background, otherwise when the store was last synced
+    pub fn sync_status(&self) -> Option<String> {
+        if let Some(refreshing) = self
+            .refreshing
+            .as_ref()
+            .filter(|r| r.refresh == Refresh::Background)
+        {
+            return Some(format!(
+                "{} syncing {}/{}",
+                SPINNER[self.spinner % SPINNER.len()],
+                refreshing.done(),
+                refreshing.months.len()
+            ));
+        }
+        let minutes = (self.now - self.last_synced?).num_minutes();
+        Some(match minutes {
+            0 => "last synced just now".to_string(),
+            m => format!("last synced {m} min ago"),
+        })
+    }
+
+    /// Reads what is shown from the store again, after it has changed
+    pub fn reload(&mut self) -> Result<()> {
+        self.clear_activity();
+        self.set_suggestions()?;
+        self.overview = self.store.get_yearly_overview(self.active_year)?;
+        if self.active_month > 0 {
+            self.overview_month = self
+                .store
+                .get_month_overview(self.active_month, self.active_year)?;
+            self.set_month_activity()?;
+        }
+        Ok(())
+    }
+
+    /// Selects the next month, staying on the last one
+    pub fn next_row(&mut self) -> Result<()> {
+        let i = match self.table_state.selected() {
+            Some(i) => (i + 1).min(self.overview.len().saturating_sub(1)),
             None => 0,
         };
-        self.set_active_month()?;
         self.table_state.select(Some(i));
-
-        Ok(())
+        self.set_active_month()
     }
 
     pub fn previous_row(&mut self) -> Result<()> {
         let i = match self.table_state.selected() {
-            Some(i) => {
-                if i != 0 {
-                    i - 1
-                } else {
-                    0
-                }
-            }
-            None => 0,
+            Some(i) if i != 0 => i - 1,
+            _ => 0,
         };
         self.table_state.select(Some(i));
         self.set_active_month()
@@ -123,13 +465,119 @@ impl Model {
 
     pub fn set_active_month(&mut self) -> Result<()> {
         self.active_month = self.table_state.selected().unwrap() as u32 + 1;
+        self.month_table_state = TableState::default();
         self.overview_month = self
             .store
             .get_month_overview(self.active_month, self.active_year)?;
+        self.set_month_activity()
+    }
+
+    /// Shows the git activity of the active month, collecting it in the background the first time,
+    /// and adds a row for every day with activity but no logged hours, so it can be filled in
+    fn set_month_activity(&mut self) -> Result<()> {
+        self.month_activity = self.month_activity();
+
+        for activity in &self.month_activity {
+            let logged = self
+                .overview_month
+                .iter()
+                .any(|m| m.date == activity.date && m.project_id == activity.project_id);
+            if !logged {
+                self.overview_month.push(Month {
+                    date: activity.date,
+                    project_name: self
+                        .store
+                        .get_project_name(&activity.project_id)
+                        .unwrap_or_else(|_| activity.project_id.clone()),
+                    project_id: activity.project_id.clone(),
+                    hours: 0.0,
+                });
+            }
+        }
+        self.overview_month.sort_by_key(|m| m.date);
+
+        Ok(())
+    }
+
+    fn month_activity(&mut self) -> Vec<DayActivity> {
+        if self.config.git.repositories.is_empty() {
+            return vec![];
+        }
+
+        let key = (self.active_year, self.active_month);
+        match self.activity_cache.get(&key) {
+            Some(Activity::Collected(activity)) => activity
+                .iter()
+                .filter(|a| match &self.active_filter {
+                    Some(f) => f.contains(&a.project_id),
+                    None => true,
+                })
+                .cloned()
+                .collect(),
+            Some(_) => vec![],
+            None => {
+                self.activity_cache.insert(key, Activity::Collecting);
+                Self::collect_activity(key, self.config.git.clone(), self.sender.clone());
+                vec![]
+            }
+        }
+    }
+
+    /// Runs `git log` for the month on a thread, which sends [`Message::Activity`] when done
+    fn collect_activity((year, month): (i32, u32), git: Git, sender: Sender<Message>) {
+        thread::spawn(move || {
+            let activity = task::catch(|| {
+                let from = chrono::NaiveDate::from_ymd_opt(year, month, 1)
+                    .ok_or_else(|| eyre!("Invalid month {year}-{month}"))?;
+                let to = from
+                    .checked_add_months(chrono::Months::new(1))
+                    .and_then(|d| d.pred_opt())
+                    .unwrap_or(from);
+                activity::collect(&git, from, to)
+            })
+            .map_err(|err| {
+                error!("{err:?}");
+                err.root_cause().to_string()
+            });
+            let _ = sender.send(Message::Activity(year, month, activity));
+        });
+    }
 
+    /// Stores the activity collected for the month, a failure is kept so git is not run again
+    /// for the month until the store is reloaded
+    pub fn set_activity(
+        &mut self,
+        year: i32,
+        month: u32,
+        activity: std::result::Result<Vec<DayActivity>, String>,
+    ) -> Result<()> {
+        let activity = match activity {
+            Ok(activity) => Activity::Collected(activity),
+            Err(err) => {
+                self.active_error_msg = Some(format!("Git activity: {err}"));
+                Activity::Failed
+            }
+        };
+        self.activity_cache.insert((year, month), activity);
+        if (year, month) == (self.active_year, self.active_month) {
+            self.overview_month = self.store.get_month_overview(month, year)?;
+            self.set_month_activity()?;
+        }
         Ok(())
     }
 
+    /// Activity for the given day and project, if any
+    pub fn activity_for(&self, month: &Month) -> Option<&DayActivity> {
+        self.month_activity
+            .iter()
+            .find(|a| a.date == month.date && a.project_id == month.project_id)
+    }
+
+    /// Forget collected git activity so it is read again from disk
+    pub fn clear_activity(&mut self) {
+        self.activity_cache.clear();
+    }
+
     pub fn set_projects(&mut self) -> Result<()> {
         self.projects = self.store.projects()?;
 
@@ -137,13 +585,78 @@ impl Model {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+/// The date of a point in time in the user's time zone
+fn local_date(now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&Local).date_naive()
+}
+
+/// Restores the active project or group, refreshing the project ids of a group
+/// since its definition in the config may have changed. Falls back to the default
+/// project of the config.
+fn load_filter(store: &Store, config: &Config) -> Result<Option<Filter>> {
+    if let Some(name) = store.active_group()? {
+        return match config.groups.get(&name) {
+            Some(project_ids) => {
+                store.insert_active_group(&name, project_ids)?;
+                Ok(Some(Filter::Group {
+                    name,
+                    project_ids: project_ids.clone(),
+                }))
+            }
+            None => {
+                store.delete_active_filter()?;
+                Ok(None)
+            }
+        };
+    }
+
+    let project = match store.default_project()? {
+        Some(project) => Some(project),
+        None => config.default_project.as_ref().and_then(|project_id| {
+            store
+                .get_project_name(project_id)
+                .ok()
+                .map(|project_name| Project {
+                    project_id: project_id.clone(),
+                    project_name,
+                })
+        }),
+    };
+    match project {
+        Some(project) => {
+            store.insert_active_project(&project.project_id)?;
+            Ok(Some(Filter::Project(project)))
+        }
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ActiveView {
     Home,
     Loading,
     Month,
     Hours,
     LogHours,
+    Timesheet,
+    History,
+    Palette,
+}
+
+impl ActiveView {
+    /// The key binding contexts handling keys in the view
+    pub fn contexts(&self) -> &'static [Context] {
+        match self {
+            ActiveView::Home => &[Context::Global, Context::Home],
+            ActiveView::Loading => &[Context::Global, Context::Loading],
+            ActiveView::Month => &[Context::Global],
+            ActiveView::Hours => &[Context::Project, Context::Picker],
+            ActiveView::LogHours => &[Context::Hours, Context::Picker],
+            ActiveView::Timesheet => &[Context::Timesheet],
+            ActiveView::History => &[Context::History],
+            ActiveView::Palette => &[Context::Picker],
+        }
+    }
 }
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -153,17 +666,54 @@ pub enum RunningState {
     Done,
 }
 
+/// Git activity of a month, collected in the background
+#[derive(Debug)]
+enum Activity {
+    Collecting,
+    Collected(Vec<DayActivity>),
+    /// Already reported, not collected again until the store is reloaded
+    Failed,
+}
+
 #[derive(Debug)]
 pub enum Message {
     View(ActiveView),
     Home,
     Hours(ProjectMessage),
     AddHours(HoursMessage),
-    ActiveProject(Option<Project>),
+    Timesheet(TimesheetMessage),
+    History(HistoryMessage),
+    Palette(PaletteMessage),
+    /// Runs an action picked in the command palette
+    Command(Context, Action),
+    ActiveFilter(Option<Filter>),
     RefreshStarted,
-    RefreshProgressing(u32),
-    RefreshCompleted,
-    RefreshFailed(String),
+    /// A month of the refresh with the generation was stored, or failed with the error
+    RefreshMonth(u64, u32, Option<String>),
+    /// Every month of the refresh with the generation was fetched
+    RefreshCompleted(u64),
+    /// Esc while refreshing, the months already stored are kept
+    RefreshCancelled,
+    /// A background task ended with an error or a panic
+    TaskFailed(TaskFailure),
+    /// The git activity of a month was collected, or failed with the error
+    Activity(i32, u32, std::result::Result<Vec<DayActivity>, String>),
+    /// Hours were submitted, with a description for the notice
+    Submitted(String),
+    Undo,
+    /// The last submission was undone, or there was nothing to undo, or it failed with the error
+    Undone(std::result::Result<Option<JournalEntry>, String>),
+    /// Switch to the next profile of the config
+    SwitchProfile,
+    /// Accept the oldest suggested recurring entry, or all of them
+    AcceptSuggestions(bool),
+    /// Suggestions were logged, until the first one failing with the error, if any
+    SuggestionsAccepted(usize, Option<String>),
+    DismissSuggestion,
     DetailMonth,
+    /// A terminal event, mapped to a message with the model
+    Input(Event),
+    /// The clock, sent periodically
+    Tick(DateTime<Utc>),
     Quit,
 }
@@ -0,0 +1,145 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+/// Second click on the same cell within this time is a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Where the rows of a table were last drawn, to find the row under the mouse
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TableArea {
+    /// The table without its borders
+    pub area: Rect,
+    /// Lines above the first row, including the header margin
+    pub header: u16,
+    pub row_height: u16,
+}
+
+impl TableArea {
+    pub fn new(area: Rect, header: u16, row_height: u16) -> Self {
+        TableArea {
+            area,
+            header,
+            row_height,
+        }
+    }
+
+    /// Index of the row at the position, counting from the first row scrolled into view.
+    /// The index may be past the last row when the table does not fill its area.
+    pub fn row(&self, event: MouseEvent, offset: usize) -> Option<usize> {
+        if !self.contains(event) || self.row_height == 0 {
+            return None;
+        }
+        let line = (event.row - self.area.y).checked_sub(self.header)?;
+        Some(offset + usize::from(line / self.row_height))
+    }
+
+    pub fn contains(&self, event: MouseEvent) -> bool {
+        self.area.contains(Position::new(event.column, event.row))
+    }
+}
+
+/// What a mouse event does to a list
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Gesture {
+    Click,
+    DoubleClick,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// Turns mouse events into gestures, remembering the last click to detect double-clicks
+#[derive(Debug, Default)]
+pub struct Clicks {
+    last: Option<(Instant, u16, u16)>,
+}
+
+impl Clicks {
+    pub fn gesture(&mut self, event: MouseEvent) -> Option<Gesture> {
+        self.gesture_at(event, Instant::now())
+    }
+
+    fn gesture_at(&mut self, event: MouseEvent, now: Instant) -> Option<Gesture> {
+        match event.kind {
+            MouseEventKind::ScrollUp => Some(Gesture::ScrollUp),
+            MouseEventKind::ScrollDown => Some(Gesture::ScrollDown),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let double = self.last.is_some_and(|(at, column, row)| {
+                    now.duration_since(at) < DOUBLE_CLICK
+                        && column == event.column
+                        && row == event.row
+                });
+                if double {
+                    self.last = None;
+                    Some(Gesture::DoubleClick)
+                } else {
+                    self.last = Some((now, event.column, event.row));
+                    Some(Gesture::Click)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+    use ratatui::layout::Rect;
+
+    use crate::mouse::{Clicks, Gesture, TableArea};
+
+    fn event(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn test_table_row() {
+        let table = TableArea::new(Rect::new(10, 5, 20, 10), 2, 2);
+        let click = |column, row| event(MouseEventKind::Down(MouseButton::Left), column, row);
+        assert_eq!(table.row(click(12, 6), 0), None, "header");
+        assert_eq!(table.row(click(12, 7), 0), Some(0));
+        assert_eq!(table.row(click(12, 8), 0), Some(0));
+        assert_eq!(table.row(click(12, 9), 3), Some(4));
+        assert_eq!(table.row(click(9, 9), 0), None, "outside");
+    }
+
+    #[test]
+    fn test_double_click() {
+        let mut clicks = Clicks::default();
+        let click = event(MouseEventKind::Down(MouseButton::Left), 3, 4);
+        let now = Instant::now();
+        assert_eq!(clicks.gesture_at(click, now), Some(Gesture::Click));
+        assert_eq!(
+            clicks.gesture_at(click, now + Duration::from_millis(100)),
+            Some(Gesture::DoubleClick)
+        );
+        assert_eq!(
+            clicks.gesture_at(click, now + Duration::from_millis(200)),
+            Some(Gesture::Click),
+            "a third click starts over"
+        );
+        assert_eq!(
+            clicks.gesture_at(click, now + Duration::from_secs(1)),
+            Some(Gesture::Click),
+            "too slow"
+        );
+        let moved = event(MouseEventKind::Down(MouseButton::Left), 5, 4);
+        assert_eq!(
+            clicks.gesture_at(moved, now + Duration::from_millis(1100)),
+            Some(Gesture::Click)
+        );
+        assert_eq!(
+            clicks.gesture_at(event(MouseEventKind::Moved, 5, 4), now),
+            None
+        );
+    }
+}
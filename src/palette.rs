@@ -0,0 +1,223 @@
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use ratatui::widgets::{Block, Cell, Clear, HighlightSpacing, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+
+use crate::fuzzy;
+use crate::keys::{Action, Command, Context, Keymap};
+use crate::model::{ActiveView, Message};
+use crate::theme::Theme;
+use crate::ui::centered_rect;
+
+/// Lists every action by name, filtered by typing
+#[derive(Debug)]
+pub struct PaletteModel {
+    keymap: Keymap,
+    theme: Theme,
+    commands: Vec<Command>,
+    matches: Vec<usize>,
+    query: String,
+    table_state: TableState,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub enum PaletteMessage {
+    Open,
+}
+
+impl PaletteModel {
+    pub fn new(keymap: &Keymap, theme: Theme) -> Self {
+        let mut model = PaletteModel {
+            keymap: keymap.clone(),
+            theme,
+            // cancelling a refresh only applies while it runs, and the picker keys only
+            // within a picker, not from the palette
+            commands: keymap
+                .commands()
+                .into_iter()
+                .filter(|c| !matches!(c.context, Context::Loading | Context::Picker))
+                .collect(),
+            matches: vec![],
+            query: String::new(),
+            table_state: TableState::default(),
+        };
+        model.filter();
+        model
+    }
+
+    /// The name typed in the palette, e.g. `timesheet next_week`
+    fn name(command: &Command) -> String {
+        match command.context {
+            Context::Global | Context::Home => command.action.to_string(),
+            context => format!("{context} {}", command.action),
+        }
+    }
+
+    /// Applies the query to the names and descriptions, best match first
+    fn filter(&mut self) {
+        let mut ranked: Vec<(i64, usize)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, command)| {
+                let name = fuzzy::score(&self.query, &Self::name(command));
+                let description = fuzzy::score(&self.query, command.description);
+                name.max(description).map(|score| (score, i))
+            })
+            .collect();
+        // stable, so equal scores keep the order of the help
+        ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        self.matches = ranked.into_iter().map(|(_, i)| i).collect();
+        self.table_state
+            .select((!self.matches.is_empty()).then_some(0));
+    }
+
+    fn selected(&self) -> Option<&Command> {
+        self.table_state
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .map(|m| &self.commands[*m])
+    }
+}
+
+pub fn handle_key(key: KeyEvent, model: &mut PaletteModel) -> Result<Option<Message>> {
+    match model.keymap.action(Context::Picker, &key) {
+        Some(Action::Close) => return Ok(Some(Message::Home)),
+        Some(Action::Select) => {
+            return Ok(model
+                .selected()
+                .map(|c| Message::Command(c.context, c.action)))
+        }
+        Some(Action::Down) => {
+            model.table_state.select_next();
+            return Ok(None);
+        }
+        Some(Action::Up) => {
+            model.table_state.select_previous();
+            return Ok(None);
+        }
+        _ => {}
+    }
+
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Backspace => {
+            model.query.pop();
+            model.filter();
+        }
+        KeyCode::Char(c) if !ctrl => {
+            model.query.push(c);
+            model.filter();
+        }
+        _ => {}
+    }
+    Ok(None)
+}
+
+pub fn update(model: &mut PaletteModel, msg: PaletteMessage) -> Result<Option<Message>> {
+    match msg {
+        PaletteMessage::Open => {
+            model.query.clear();
+            model.filter();
+            Ok(Some(Message::View(ActiveView::Palette)))
+        }
+    }
+}
+
+pub fn render(f: &mut Frame, model: &mut PaletteModel, area: Rect) {
+    let theme = model.theme;
+    let area = centered_rect(60, 60, area);
+    f.render_widget(Clear, area);
+
+    let [input_area, table_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area);
+
+    let input = Paragraph::new(format!(":{}", model.query))
+        .block(Block::bordered().title("Command palette"))
+        .style(theme.border);
+    f.render_widget(input, input_area);
+    f.set_cursor_position(Position::new(
+        input_area.x + model.query.chars().count() as u16 + 2,
+        input_area.y + 1,
+    ));
+
+    let rows = model.matches.iter().enumerate().map(|(i, m)| {
+        let command = &model.commands[*m];
+        Row::new(vec![
+            Cell::from(PaletteModel::name(command)),
+            Cell::from(command.description),
+            Cell::from(command.keys.clone()).style(theme.key),
+        ])
+        .style(theme.alternate(i))
+    });
+    let t = Table::new(
+        rows,
+        [
+            Constraint::Fill(2),
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+        ],
+    )
+    .block(Block::bordered().title_bottom(format!(
+        "Run {}   {}",
+        model.keymap.label(Context::Picker, Action::Select),
+        model.keymap.hints(Context::Picker, &[Action::Close])
+    )))
+    .row_highlight_style(theme.selected)
+    .style(theme.fill(model.matches.len()))
+    .highlight_spacing(HighlightSpacing::Always);
+    f.render_stateful_widget(t, table_area, &mut model.table_state);
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::keys::{Action, Context, Keymap};
+    use crate::model::Message;
+    use crate::palette::{handle_key, PaletteModel};
+    use crate::theme::Theme;
+
+    fn type_query(model: &mut PaletteModel, query: &str) {
+        for c in query.chars() {
+            handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), model).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_palette_search() {
+        let mut model = PaletteModel::new(&Keymap::default(), Theme::dark());
+        let listed = Keymap::default()
+            .commands()
+            .iter()
+            .filter(|c| !matches!(c.context, Context::Loading | Context::Picker))
+            .count();
+        assert_eq!(model.matches.len(), listed);
+        assert!(model.commands.iter().all(|c| c.context != Context::Loading));
+
+        type_query(&mut model, "next week");
+        let selected = model.selected().unwrap();
+        assert_eq!(
+            (selected.context, selected.action),
+            (Context::Timesheet, Action::NextWeek)
+        );
+
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(matches!(
+            handle_key(enter, &mut model).unwrap(),
+            Some(Message::Command(Context::Timesheet, Action::NextWeek))
+        ));
+    }
+
+    #[test]
+    fn test_palette_description() {
+        let mut model = PaletteModel::new(&Keymap::default(), Theme::dark());
+        type_query(&mut model, "quit app");
+        assert_eq!(model.selected().unwrap().action, Action::Quit);
+
+        type_query(&mut model, "zzz");
+        assert!(model.selected().is_none());
+    }
+}
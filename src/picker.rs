@@ -0,0 +1,417 @@
+use chrono::NaiveDate;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use ratatui::text::Text;
+use ratatui::widgets::{
+    Block, BorderType, Cell, HighlightSpacing, Paragraph, Row, Table, TableState,
+};
+use ratatui::Frame;
+
+use crate::fuzzy;
+use crate::keys::{Action, Context, Keymap};
+use crate::mouse::{Gesture, TableArea};
+use crate::store::{Filter, ProjectUsage};
+use crate::theme::Theme;
+
+/// Outcome of a key press in the picker
+#[derive(Debug, PartialEq)]
+pub enum PickerEvent {
+    Selected(Filter),
+    Cancelled,
+}
+
+/// A project or project group listed in the picker
+#[derive(Debug, Clone)]
+pub struct PickerItem {
+    pub filter: Filter,
+    pub month_hours: f32,
+    pub last_used: Option<NaiveDate>,
+}
+
+impl PickerItem {
+    /// Combines the usage of every project in the group
+    pub fn group(name: &str, project_ids: &[String], usage: &[ProjectUsage]) -> Self {
+        let members = usage
+            .iter()
+            .filter(|u| project_ids.contains(&u.project.project_id));
+        PickerItem {
+            filter: Filter::Group {
+                name: name.to_string(),
+                project_ids: project_ids.to_vec(),
+            },
+            month_hours: members.clone().map(|u| u.month_hours).sum(),
+            last_used: members.filter_map(|u| u.last_used).max(),
+        }
+    }
+
+    fn label(&self) -> String {
+        match &self.filter {
+            Filter::Project(p) => p.project_name.clone(),
+            Filter::Group { name, .. } => format!("{name} (group)"),
+        }
+    }
+}
+
+impl From<ProjectUsage> for PickerItem {
+    fn from(usage: ProjectUsage) -> Self {
+        PickerItem {
+            filter: Filter::Project(usage.project),
+            month_hours: usage.month_hours,
+            last_used: usage.last_used,
+        }
+    }
+}
+
+/// Type-to-filter list of projects, ranked by match score and recent usage
+#[derive(Debug, Default)]
+pub struct ProjectPicker {
+    pub table_state: TableState,
+    items: Vec<PickerItem>,
+    matches: Vec<usize>,
+    query: String,
+    today: Option<NaiveDate>,
+    /// Where the picker was last drawn, for the mouse
+    area: Rect,
+    table: TableArea,
+}
+
+impl ProjectPicker {
+    /// Replaces the items in the picker and resets the query
+    pub fn set_items(&mut self, items: Vec<PickerItem>, today: NaiveDate) {
+        self.items = items;
+        self.today = Some(today);
+        self.query.clear();
+        self.filter();
+    }
+
+    /// Moves the selection to the given project or group, if it is listed
+    pub fn select(&mut self, filter: &Filter) {
+        if let Some(i) = self
+            .matches
+            .iter()
+            .position(|m| self.items[*m].filter == *filter)
+        {
+            self.table_state.select(Some(i));
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Filter> {
+        self.table_state
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .map(|m| &self.items[*m].filter)
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Applies the query, keeping matching items ordered by score, recency and name
+    fn filter(&mut self) {
+        let mut ranked: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| {
+                fuzzy::score(&self.query, item.filter.name())
+                    .map(|score| (score + self.recency(item), i))
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.0.cmp(&a.0).then_with(|| {
+                self.items[a.1]
+                    .filter
+                    .name()
+                    .cmp(self.items[b.1].filter.name())
+            })
+        });
+
+        self.matches = ranked.into_iter().map(|(_, i)| i).collect();
+        self.table_state.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    // projects used within the last 30 days get a bonus that decays per day
+    fn recency(&self, item: &PickerItem) -> i64 {
+        match (self.today, item.last_used) {
+            (Some(today), Some(last_used)) => (30 - (today - last_used).num_days()).clamp(0, 30),
+            _ => 0,
+        }
+    }
+
+    fn next_row(&mut self) {
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.matches.len() => i + 1,
+            Some(i) => i,
+            None => 0,
+        };
+        self.table_state.select(Some(i));
+    }
+
+    fn previous_row(&mut self) {
+        let i = match self.table_state.selected() {
+            Some(i) if i != 0 => i - 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+    }
+
+    /// Scrolling moves the selection, clicking selects a row and double-clicking picks it.
+    /// Clicking outside the picker cancels it.
+    pub fn handle_mouse(&mut self, event: MouseEvent, gesture: Gesture) -> Option<PickerEvent> {
+        match gesture {
+            Gesture::ScrollDown => self.next_row(),
+            Gesture::ScrollUp => self.previous_row(),
+            _ if !self.area.contains(Position::new(event.column, event.row)) => {
+                return Some(PickerEvent::Cancelled)
+            }
+            Gesture::Click | Gesture::DoubleClick => {
+                let row = self
+                    .table
+                    .row(event, self.table_state.offset())
+                    .filter(|row| *row < self.matches.len())?;
+                self.table_state.select(Some(row));
+                if gesture == Gesture::DoubleClick {
+                    return self.selected().cloned().map(PickerEvent::Selected);
+                }
+            }
+        }
+        None
+    }
+
+    /// Handles typing, navigation and selection with the keys of the picker context.
+    /// Keys that the picker does not use are ignored, allowing the owner to handle them first.
+    pub fn handle_key(&mut self, key: KeyEvent, keymap: &Keymap) -> Option<PickerEvent> {
+        match keymap.action(Context::Picker, &key) {
+            Some(Action::Close) => return Some(PickerEvent::Cancelled),
+            Some(Action::Select) => return self.selected().cloned().map(PickerEvent::Selected),
+            Some(Action::Down) => {
+                self.next_row();
+                return None;
+            }
+            Some(Action::Up) => {
+                self.previous_row();
+                return None;
+            }
+            _ => {}
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.filter();
+                None
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.filter();
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+pub fn render(
+    f: &mut Frame,
+    picker: &mut ProjectPicker,
+    theme: &Theme,
+    area: Rect,
+    title: &str,
+    footer: &str,
+) {
+    let [input_area, table_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area);
+
+    let input = Paragraph::new(picker.query.as_str())
+        .block(Block::bordered().title(title.to_string()))
+        .style(theme.border);
+    f.render_widget(input, input_area);
+    f.set_cursor_position(Position::new(
+        input_area.x + picker.query.chars().count() as u16 + 1,
+        input_area.y + 1,
+    ));
+
+    let header = ["Project", "This month"]
+        .into_iter()
+        .map(ratatui::widgets::Cell::from)
+        .collect::<Row>()
+        .style(theme.alternate(1))
+        .height(1);
+    let rows = picker.matches.iter().enumerate().map(|(i, m)| {
+        let item = &picker.items[*m];
+        vec![item.label(), format!("{:.1}", item.month_hours)]
+            .into_iter()
+            .map(|content| Cell::from(Text::from(content)))
+            .collect::<Row>()
+            .style(theme.alternate(i))
+            .height(1)
+    });
+    let bar = " █ ";
+    let block = Block::bordered().border_type(BorderType::Plain);
+    let rows_area = block.inner(table_area);
+    picker.area = area;
+    // the footer takes the last line
+    picker.table = TableArea::new(
+        Rect {
+            height: rows_area.height.saturating_sub(1),
+            ..rows_area
+        },
+        1,
+        1,
+    );
+    let t = Table::new(rows, [Constraint::Min(2), Constraint::Length(10 + 1)])
+        .header(header)
+        .block(block)
+        .row_highlight_style(theme.selected)
+        .highlight_symbol(Text::from(vec![bar.into(), bar.into()]))
+        .footer(Row::new(vec![Cell::new(footer.to_string())]))
+        .style(theme.fill(picker.matches.len()))
+        .highlight_spacing(HighlightSpacing::Always);
+    f.render_stateful_widget(t, table_area, &mut picker.table_state);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use crossterm::event::{
+        KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    };
+    use ratatui::layout::Rect;
+
+    use crate::keys::Keymap;
+    use crate::mouse::{Gesture, TableArea};
+    use crate::picker::{PickerEvent, PickerItem, ProjectPicker};
+    use crate::store::{Filter, Project, ProjectUsage};
+
+    fn usage(name: &str, last_used: Option<NaiveDate>) -> ProjectUsage {
+        ProjectUsage {
+            project: Project {
+                project_id: name.to_lowercase(),
+                project_name: name.to_string(),
+            },
+            month_hours: 1.0,
+            last_used,
+        }
+    }
+
+    fn type_query(picker: &mut ProjectPicker, query: &str) {
+        for c in query.chars() {
+            picker.handle_key(
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+                &Keymap::default(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_picker_ranking() {
+        let today = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+        let mut picker = ProjectPicker::default();
+        picker.set_items(
+            vec![
+                usage("Alpha", None).into(),
+                usage("Beta", today.pred_opt()).into(),
+                usage("Gamma", None).into(),
+            ],
+            today,
+        );
+        assert_eq!(
+            picker.selected().unwrap().name(),
+            "Beta",
+            "recently used projects come first"
+        );
+
+        type_query(&mut picker, "gm");
+        assert_eq!(picker.selected().unwrap().name(), "Gamma");
+
+        type_query(&mut picker, "x");
+        assert!(picker.selected().is_none());
+        assert_eq!(
+            picker.handle_key(
+                KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+                &Keymap::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_picker_select() {
+        let today = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+        let mut picker = ProjectPicker::default();
+        let beta = usage("Beta", None);
+        picker.set_items(
+            vec![usage("Alpha", None).into(), beta.clone().into()],
+            today,
+        );
+        picker.select(&Filter::Project(beta.project.clone()));
+
+        let event = picker.handle_key(
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+            &Keymap::default(),
+        );
+        assert_eq!(
+            event,
+            Some(PickerEvent::Selected(Filter::Project(beta.project)))
+        );
+    }
+
+    #[test]
+    fn test_picker_mouse() {
+        let today = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+        let mut picker = ProjectPicker::default();
+        let beta = usage("Beta", None);
+        picker.set_items(
+            vec![usage("Alpha", None).into(), beta.clone().into()],
+            today,
+        );
+        picker.area = Rect::new(0, 0, 20, 10);
+        picker.table = TableArea::new(Rect::new(1, 4, 18, 4), 1, 1);
+        let click = |column, row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+
+        assert_eq!(picker.handle_mouse(click(2, 6), Gesture::Click), None);
+        assert_eq!(
+            picker.selected(),
+            Some(&Filter::Project(beta.project.clone()))
+        );
+        assert_eq!(
+            picker.handle_mouse(click(2, 6), Gesture::DoubleClick),
+            Some(PickerEvent::Selected(Filter::Project(beta.project)))
+        );
+        assert_eq!(
+            picker.handle_mouse(click(2, 7), Gesture::Click),
+            None,
+            "below the last row"
+        );
+        assert_eq!(
+            picker.handle_mouse(click(30, 6), Gesture::Click),
+            Some(PickerEvent::Cancelled)
+        );
+    }
+
+    #[test]
+    fn test_picker_group() {
+        let today = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+        let usage = vec![
+            usage("Alpha", None),
+            usage("Beta", today.pred_opt()),
+            usage("Gamma", None),
+        ];
+        let group = PickerItem::group("Customer", &["alpha".into(), "beta".into()], &usage);
+
+        assert_eq!(group.month_hours, 2.0);
+        assert_eq!(group.last_used, today.pred_opt());
+        assert!(group.filter.contains("alpha"));
+        assert!(!group.filter.contains("gamma"));
+    }
+}
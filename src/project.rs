@@ -1,18 +1,28 @@
+use std::collections::BTreeMap;
+
+use crate::keys::{Action, Context, Keymap};
 use crate::model::{ActiveView, Message};
-use crate::store::{Project, Store};
-use crate::ui::{alternate_color, centered_rect, fill_color, SELECTED_COLOR};
+use crate::mouse::Gesture;
+use crate::picker::{self, PickerEvent, PickerItem, ProjectPicker};
+use crate::store::Store;
+use crate::theme::Theme;
+use crate::ui::centered_rect;
+use chrono::{Datelike, NaiveDate};
 use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use ratatui::layout::{Constraint, Rect};
-use ratatui::text::Text;
-use ratatui::widgets::{Block, BorderType, Cell, Clear, HighlightSpacing, Row, Table, TableState};
+use crossterm::event::{KeyEvent, MouseEvent};
+use ratatui::layout::Rect;
+use ratatui::widgets::Clear;
 use ratatui::Frame;
 
 #[derive(Debug)]
 pub struct ProjectModel {
     store: Store,
-    pub table_state: TableState,
-    pub projects: Vec<Project>,
+    groups: BTreeMap<String, Vec<String>>,
+    keymap: Keymap,
+    theme: Theme,
+    pub picker: ProjectPicker,
+    /// The date in the user's time zone, on the clock of the model
+    pub today: NaiveDate,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash)]
@@ -22,78 +32,67 @@ pub enum ProjectMessage {
 }
 
 impl ProjectModel {
-    pub fn new(store: Store) -> Result<Self> {
+    pub fn new(
+        store: Store,
+        groups: BTreeMap<String, Vec<String>>,
+        keymap: Keymap,
+        theme: Theme,
+        today: NaiveDate,
+    ) -> Result<Self> {
         Ok(ProjectModel {
             store,
-            table_state: TableState::default().with_selected(0),
-            projects: Vec::with_capacity(4), // most users have at least 4 possible projects
+            groups,
+            keymap,
+            theme,
+            picker: ProjectPicker::default(),
+            today,
         })
     }
 
     fn set_projects(&mut self) -> Result<()> {
-        self.projects = self.store.projects()?;
+        let today = self.today;
+        let usage = self.store.project_usage(today.month(), today.year())?;
+        let groups = self
+            .groups
+            .iter()
+            .map(|(name, project_ids)| PickerItem::group(name, project_ids, &usage));
+        let items = groups
+            .chain(usage.iter().cloned().map(PickerItem::from))
+            .collect();
+        self.picker.set_items(items, today);
         Ok(())
     }
+}
 
-    fn set_active_project(&self) -> Result<Project> {
-        let project = self
-            .projects
-            .get(self.table_state.selected().unwrap())
-            .unwrap();
-        self.store.insert_active_project(&project.project_id)?;
-
-        Ok(project.clone())
+pub fn handle_key(key: KeyEvent, model: &mut ProjectModel) -> Result<Option<Message>> {
+    if let Some(action) = model.keymap.action(Context::Project, &key) {
+        return Ok(run_action(action));
     }
 
-    pub fn next_row(&mut self) -> Result<()> {
-        let i = match self.table_state.selected() {
-            Some(i) => {
-                if i == self.projects.len() {
-                    i
-                } else {
-                    i + 1
-                }
-            }
-
-            None => 0,
-        };
-        self.table_state.select(Some(i));
-
-        Ok(())
+    match model.picker.handle_key(key, &model.keymap) {
+        Some(PickerEvent::Selected(filter)) => Ok(Some(Message::ActiveFilter(Some(filter)))),
+        Some(PickerEvent::Cancelled) => Ok(Some(Message::Home)),
+        None => Ok(None),
     }
+}
 
-    pub fn previous_row(&mut self) -> Result<()> {
-        let i = match self.table_state.selected() {
-            Some(i) => {
-                if i != 0 {
-                    i - 1
-                } else {
-                    0
-                }
-            }
-            None => 0,
-        };
-        self.table_state.select(Some(i));
-        Ok(())
+/// Runs an action of the project keys, also when picked in the command palette
+pub fn run_action(action: Action) -> Option<Message> {
+    match action {
+        Action::ClearFilter => Some(Message::ActiveFilter(None)),
+        _ => None,
     }
 }
 
-pub fn handle_key(key: KeyEvent, model: &mut ProjectModel) -> Result<Option<Message>> {
-    match key.code {
-        KeyCode::Char('H') => Ok(Some(Message::Home)),
-        KeyCode::Char('j') => {
-            model.next_row()?;
-            Ok(None)
-        }
-        KeyCode::Char('k') => {
-            model.previous_row()?;
-            Ok(None)
-        }
-        KeyCode::Char('x') => Ok(Some(Message::ActiveProject(None))),
-        KeyCode::Enter => Ok(Some(Message::ActiveProject(Some(
-            model.set_active_project()?,
-        )))),
-        _ => Ok(None),
+pub fn handle_mouse(
+    event: MouseEvent,
+    gesture: Gesture,
+    model: &mut ProjectModel,
+) -> Option<Message> {
+    match model.picker.handle_mouse(event, gesture) {
+        Some(PickerEvent::Selected(filter)) => Some(Message::ActiveFilter(Some(filter))),
+        Some(PickerEvent::Cancelled) => Some(Message::Home),
+        None => None,
     }
 }
 
@@ -110,34 +109,84 @@ pub fn update(model: &mut ProjectModel, msg: ProjectMessage) -> Result<Option<Me
 pub fn render(f: &mut Frame, model: &mut ProjectModel, area: Rect) {
     f.render_widget(Clear, area);
 
-    let area = centered_rect(40, 20, area);
-
-    let header = ["Project"]
-        .into_iter()
-        .map(ratatui::widgets::Cell::from)
-        .collect::<Row>()
-        .style(alternate_color(1))
-        .height(1);
-    let rows = model.projects.iter().enumerate().map(|(i, data)| {
-        vec![&data.project_name]
-            .into_iter()
-            .map(|content| Cell::from(Text::from(content.to_string())))
-            .collect::<Row>()
-            .style(alternate_color(i))
-            .height(1)
-    });
-    let bar = " █ ";
-    let t = Table::new(rows, [Constraint::Min(2), Constraint::Length(15 + 1)])
-        .header(header)
-        .block(
-            Block::bordered()
-                .border_type(BorderType::Plain)
-                .title("Select active project"),
-        )
-        .row_highlight_style(SELECTED_COLOR)
-        .highlight_symbol(Text::from(vec![bar.into(), bar.into()]))
-        .footer(Row::new(vec![Cell::new("Set <Enter>   Unset <x>")]))
-        .style(fill_color(model.projects.len()))
-        .highlight_spacing(HighlightSpacing::Always);
-    f.render_stateful_widget(t, area, &mut model.table_state);
+    let area = centered_rect(40, 40, area);
+
+    picker::render(
+        f,
+        &mut model.picker,
+        &model.theme,
+        area,
+        "Select active project or group",
+        &format!(
+            "Set {}   {}   {}",
+            model.keymap.label(Context::Picker, Action::Select),
+            model.keymap.hints(Context::Project, &[Action::ClearFilter]),
+            model.keymap.hints(Context::Picker, &[Action::Close])
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use crate::harness::Harness;
+    use crate::keys::Keymap;
+    use crate::model::{ActiveView, Message};
+    use crate::project::ProjectMessage;
+    use crate::store::Filter;
+
+    #[test]
+    fn test_render_picker() {
+        let mut harness = Harness::new();
+        harness.send(Message::Hours(ProjectMessage::Open));
+        assert_eq!(harness.model.active_view, ActiveView::Hours);
+        harness.assert_snapshot("project_picker", 100, 30);
+
+        harness.type_str("proj g");
+        harness.assert_snapshot("project_picker_search", 100, 30);
+    }
+
+    #[test]
+    fn test_set_filter() {
+        let mut harness = Harness::new();
+        harness
+            .send(Message::Hours(ProjectMessage::Open))
+            .type_str("proj g")
+            .key(KeyCode::Enter);
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert!(matches!(
+            &harness.model.active_filter,
+            Some(Filter::Project(p)) if p.project_name == "Project G"
+        ));
+        harness.assert_snapshot("home_filtered", 100, 30);
+    }
+
+    #[test]
+    fn test_picker_keys() {
+        let mut harness = Harness::configured(|config| {
+            config.keys = toml::from_str("[picker]\nselect = 'Tab'").unwrap();
+            config.keymap = Keymap::new(&config.keys).unwrap();
+        });
+        harness
+            .send(Message::Hours(ProjectMessage::Open))
+            .type_str("proj g")
+            .key(KeyCode::Enter);
+        assert_eq!(
+            harness.model.active_view,
+            ActiveView::Hours,
+            "Enter no longer selects"
+        );
+        assert!(harness
+            .render(200, 30)
+            .iter()
+            .any(|line| line.contains("Set <Tab>   Unset <Ctrl-x>   Close <Esc>")));
+
+        harness.key(KeyCode::Tab);
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert!(matches!(
+            &harness.model.active_filter,
+            Some(Filter::Project(p)) if p.project_name == "Project G"
+        ));
+    }
 }
@@ -0,0 +1,321 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use thiserror::Error;
+
+use crate::config::Recurring;
+use crate::store::DayEntry;
+use crate::timesheet::week_start;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RecurringError {
+    #[error("Invalid recurring rule '{rule}': {reason}")]
+    InvalidRule { rule: String, reason: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// A subset of RRULE (RFC 5545): `FREQ`, `INTERVAL`, `BYDAY` and `BYMONTHDAY`
+#[derive(Debug, Clone, PartialEq)]
+struct Pattern {
+    frequency: Frequency,
+    interval: u32,
+    by_day: Vec<Weekday>,
+    by_month_day: Vec<u32>,
+}
+
+impl Pattern {
+    fn parse(rule: &str) -> Result<Self, RecurringError> {
+        let invalid = |reason: &str| RecurringError::InvalidRule {
+            rule: rule.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let mut frequency = None;
+        let mut pattern = Pattern {
+            frequency: Frequency::Weekly,
+            interval: 1,
+            by_day: vec![],
+            by_month_day: vec![],
+        };
+        for part in rule.split(';').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid("expected KEY=VALUE"))?;
+            match key.trim().to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.trim().to_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(invalid("FREQ must be DAILY, WEEKLY or MONTHLY")),
+                    });
+                }
+                "INTERVAL" => {
+                    pattern.interval = value
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|i| *i > 0)
+                        .ok_or_else(|| invalid("INTERVAL must be a positive number"))?;
+                }
+                "BYDAY" => {
+                    pattern.by_day = value
+                        .split(',')
+                        .map(|d| parse_weekday(d.trim()).ok_or_else(|| invalid("unknown BYDAY")))
+                        .collect::<Result<_, _>>()?;
+                }
+                "BYMONTHDAY" => {
+                    pattern.by_month_day = value
+                        .split(',')
+                        .map(|d| {
+                            d.trim()
+                                .parse()
+                                .ok()
+                                .filter(|d| (1..=31).contains(d))
+                                .ok_or_else(|| invalid("BYMONTHDAY must be 1...31"))
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                _ => return Err(invalid("unsupported part")),
+            }
+        }
+        pattern.frequency = frequency.ok_or_else(|| invalid("FREQ is required"))?;
+
+        Ok(pattern)
+    }
+
+    fn matches(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if date < start {
+            return false;
+        }
+        let interval = i64::from(self.interval);
+        match self.frequency {
+            Frequency::Daily => {
+                (date - start).num_days() % interval == 0
+                    && (self.by_day.is_empty() || self.by_day.contains(&date.weekday()))
+            }
+            Frequency::Weekly => {
+                let weeks = (week_start(date) - week_start(start)).num_days() / 7;
+                let by_day = if self.by_day.is_empty() {
+                    &vec![start.weekday()]
+                } else {
+                    &self.by_day
+                };
+                weeks % interval == 0 && by_day.contains(&date.weekday())
+            }
+            Frequency::Monthly => {
+                let months = i64::from(date.year() - start.year()) * 12 + i64::from(date.month())
+                    - i64::from(start.month());
+                let by_month_day = if self.by_month_day.is_empty() {
+                    &vec![start.day()]
+                } else {
+                    &self.by_month_day
+                };
+                months % interval == 0 && by_month_day.contains(&date.day())
+            }
+        }
+    }
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    match day.to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => day.parse().ok(),
+    }
+}
+
+/// The pattern of a rule, `weekdays` being shorthand for a weekly rule
+fn pattern(recurring: &Recurring) -> Result<Pattern, RecurringError> {
+    match &recurring.rule {
+        // one of them would silently be ignored
+        Some(rule) if !recurring.weekdays.is_empty() => Err(RecurringError::InvalidRule {
+            rule: rule.clone(),
+            reason: format!(
+                "set either rule or weekdays for {}, not both",
+                recurring.project
+            ),
+        }),
+        Some(rule) => Pattern::parse(rule),
+        None if !recurring.weekdays.is_empty() => Ok(Pattern {
+            frequency: Frequency::Weekly,
+            interval: 1,
+            by_day: recurring.weekdays.clone(),
+            by_month_day: vec![],
+        }),
+        None => Err(RecurringError::InvalidRule {
+            rule: String::new(),
+            reason: format!(
+                "either rule or weekdays must be set for {}",
+                recurring.project
+            ),
+        }),
+    }
+}
+
+/// Validates every rule, so mistakes are reported when the config is loaded
+pub fn validate(rules: &[Recurring]) -> Result<(), RecurringError> {
+    rules.iter().try_for_each(|r| pattern(r).map(|_| ()))
+}
+
+/// Dates between `from` and `to` (inclusive) on which the rule applies
+pub fn occurrences(
+    recurring: &Recurring,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<NaiveDate>, RecurringError> {
+    let pattern = pattern(recurring)?;
+    Ok(from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .filter(|d| recurring.end.is_none_or(|end| *d <= end))
+        .filter(|d| pattern.matches(recurring.start, *d))
+        .collect())
+}
+
+/// Hours proposed by a recurring rule which have not been logged yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub date: NaiveDate,
+    pub project_id: String,
+    /// Defaults to the project id until resolved from the store
+    pub project_name: String,
+    pub hours: f32,
+}
+
+/// Suggestions between `from` and `to` for days where the project has no hours logged
+/// and the suggestion has not been dismissed
+pub fn pending(
+    rules: &[Recurring],
+    entries: &[DayEntry],
+    dismissed: &[(NaiveDate, String)],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<Suggestion>, RecurringError> {
+    let mut suggestions = vec![];
+    for rule in rules {
+        for date in occurrences(rule, from, to)? {
+            let logged = entries
+                .iter()
+                .any(|e| e.date == date && e.project_id == rule.project && e.hours > 0.0);
+            let is_dismissed = dismissed
+                .iter()
+                .any(|(d, p)| *d == date && *p == rule.project);
+            if !logged && !is_dismissed {
+                suggestions.push(Suggestion {
+                    date,
+                    project_id: rule.project.clone(),
+                    project_name: rule.project.clone(),
+                    hours: rule.hours,
+                });
+            }
+        }
+    }
+    suggestions.sort_by_key(|s| s.date);
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Weekday};
+
+    use crate::config::Recurring;
+    use crate::recurring::{occurrences, pending, validate};
+    use crate::store::DayEntry;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn rule(rule: Option<&str>, weekdays: Vec<Weekday>) -> Recurring {
+        Recurring {
+            project: "internal".to_string(),
+            hours: 1.0,
+            weekdays,
+            rule: rule.map(str::to_string),
+            start: date(4, 1),
+            end: None,
+        }
+    }
+
+    #[test]
+    fn test_occurrences_weekdays() {
+        let r = rule(None, vec![Weekday::Mon]);
+        let dates = occurrences(&r, date(4, 1), date(4, 30)).unwrap();
+        assert_eq!(
+            dates,
+            vec![
+                date(4, 1),
+                date(4, 8),
+                date(4, 15),
+                date(4, 22),
+                date(4, 29)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_occurrences_rule() {
+        let r = rule(Some("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR"), vec![]);
+        let dates = occurrences(&r, date(4, 1), date(4, 21)).unwrap();
+        assert_eq!(
+            dates,
+            vec![date(4, 1), date(4, 5), date(4, 15), date(4, 19)]
+        );
+
+        let r = rule(Some("FREQ=MONTHLY;BYMONTHDAY=15"), vec![]);
+        let dates = occurrences(&r, date(3, 1), date(5, 31)).unwrap();
+        assert_eq!(
+            dates,
+            vec![date(4, 15), date(5, 15)],
+            "starts at start date"
+        );
+
+        let mut r = rule(Some("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR"), vec![]);
+        r.end = Some(date(4, 3));
+        let dates = occurrences(&r, date(4, 1), date(4, 7)).unwrap();
+        assert_eq!(dates, vec![date(4, 1), date(4, 2), date(4, 3)]);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[rule(Some("FREQ=WEEKLY;BYDAY=MO"), vec![])]).is_ok());
+        assert!(validate(&[rule(Some("BYDAY=MO"), vec![])]).is_err());
+        assert!(validate(&[rule(Some("FREQ=YEARLY"), vec![])]).is_err());
+        assert!(validate(&[rule(Some("FREQ=WEEKLY;BYDAY=XX"), vec![])]).is_err());
+        assert!(validate(&[rule(None, vec![])]).is_err());
+        assert_eq!(
+            validate(&[rule(Some("FREQ=DAILY"), vec![Weekday::Mon])])
+                .unwrap_err()
+                .to_string(),
+            "Invalid recurring rule 'FREQ=DAILY': set either rule or weekdays for internal, not both"
+        );
+    }
+
+    #[test]
+    fn test_pending() {
+        let rules = vec![rule(None, vec![Weekday::Mon])];
+        let entries = vec![DayEntry {
+            id: 1,
+            date: date(4, 1),
+            hours: 1.0,
+            project_name: "Internal".to_string(),
+            project_id: "internal".to_string(),
+        }];
+        let dismissed = vec![(date(4, 8), "internal".to_string())];
+
+        let suggestions = pending(&rules, &entries, &dismissed, date(4, 1), date(4, 20)).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].date, date(4, 15));
+        assert_eq!(suggestions[0].hours, 1.0);
+    }
+}
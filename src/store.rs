@@ -7,9 +7,19 @@ use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{Connection, OptionalExtension};
 use std::str::FromStr;
 
-use crate::api::TimetEntry;
+use crate::api::{Hours, TimetEntry, TimetProject};
 use crate::config::Config;
 
+/// The project table, or the projects hours were logged on until a refresh has filled it,
+/// e.g. right after upgrading from a version without the table
+const CATALOGUE: &str = r#"
+    WITH catalogue AS (
+        SELECT project_id, project_name, archived FROM project
+        UNION
+        SELECT DISTINCT project_id, project_name, 0 FROM entry
+        WHERE hours IS NOT 0 AND NOT EXISTS (SELECT 1 FROM project)
+    )"#;
+
 #[derive(Debug, Clone)]
 pub struct Store {
     pool: r2d2::Pool<SqliteConnectionManager>,
@@ -17,7 +27,9 @@ pub struct Store {
 
 impl Store {
     pub fn new(config: &Config) -> Result<Self> {
-        let manager = SqliteConnectionManager::file(format!("{}/timet.db", config.config_location));
+        let location = config.data_location();
+        std::fs::create_dir_all(&location)?;
+        let manager = SqliteConnectionManager::file(format!("{location}/timet.db"));
         let pool = r2d2::Pool::new(manager)?;
 
         let s = Store { pool };
@@ -26,6 +38,18 @@ impl Store {
         Ok(s)
     }
 
+    /// A database kept in memory, for tests. A single connection is shared
+    /// since every in-memory connection opens a database of its own.
+    pub fn memory() -> Result<Self> {
+        let manager = SqliteConnectionManager::memory();
+        let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+
+        let s = Store { pool };
+        s.create_db()?;
+
+        Ok(s)
+    }
+
     fn create_db(&self) -> Result<()> {
         let conn = &self.pool.get()?;
         conn.execute(
@@ -40,6 +64,82 @@ impl Store {
             (), // empty list of parameters.
         )?;
 
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS project (
+            project_id TEXT PRIMARY KEY,
+            project_name TEXT NOT NULL,
+            archived INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS filter_project (
+            project_id TEXT PRIMARY KEY
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS dismissed_suggestion (
+            date TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            PRIMARY KEY (date, project_id)
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS journal (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created TEXT NOT NULL,
+            date TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            previous_hours REAL,
+            hours REAL NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+            (),
+        )?;
+
+        conn.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS submission_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            submitted TEXT NOT NULL,
+            date TEXT NOT NULL,
+            project_id TEXT NOT NULL,
+            hours REAL NOT NULL,
+            source TEXT NOT NULL,
+            note TEXT,
+            status_code INTEGER,
+            response TEXT NOT NULL
+            )
+            "#,
+            (),
+        )?;
+
+        // the submission log is proof of what was submitted, so it is never changed
+        conn.execute_batch(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS submission_log_no_update
+            BEFORE UPDATE ON submission_log
+            BEGIN SELECT RAISE(ABORT, 'submission_log is append-only'); END;
+
+            CREATE TRIGGER IF NOT EXISTS submission_log_no_delete
+            BEFORE DELETE ON submission_log
+            BEGIN SELECT RAISE(ABORT, 'submission_log is append-only'); END;
+            "#,
+        )?;
+
         conn.execute(
             r#"
             CREATE TABLE IF NOT EXISTS config (
@@ -50,6 +150,16 @@ impl Store {
             (),
         )?;
 
+        // the active project was only kept in config before filters were stored as project ids
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO filter_project (project_id)
+            SELECT value FROM config WHERE key = 'active_project'
+            AND NOT EXISTS (SELECT 1 FROM filter_project)
+            "#,
+            (),
+        )?;
+
         Ok(())
     }
 
@@ -72,12 +182,13 @@ impl Store {
 
     pub fn default_project(&self) -> Result<Option<Project>> {
         let conn = &self.pool.get()?;
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare(&format!(
             r#"
-            SELECT project_id, project_name FROM entry 
+            {CATALOGUE}
+            SELECT project_id, project_name FROM catalogue
             WHERE project_id = (SELECT value FROM config WHERE key = 'active_project')
-             "#,
-        )?;
+             "#
+        ))?;
 
         stmt.query_row([], |row| {
             Ok(Project {
@@ -89,54 +200,116 @@ impl Store {
         .map_err(|err| color_eyre::Report::new(err))
     }
 
-    pub fn delete_active_project(&self) -> Result<()> {
+    /// Name of the active project group, if a group is the active filter
+    pub fn active_group(&self) -> Result<Option<String>> {
         let conn = &self.pool.get()?;
-        let mut stmt = conn.prepare("DELETE FROM config WHERE key = 'active_project'")?;
-        let result = stmt.execute([])?;
-        Ok(())
+        let mut stmt = conn.prepare("SELECT value FROM config WHERE key = 'active_group'")?;
+
+        stmt.query_row([], |row| row.get(0))
+            .optional()
+            .map_err(|err| color_eyre::Report::new(err))
+    }
+
+    /// Removes the active project or group, so overviews include all projects
+    pub fn delete_active_filter(&self) -> Result<()> {
+        self.set_filter(None, &[])
     }
 
     pub fn insert_active_project(&self, project_id: &str) -> Result<()> {
-        let conn = &self.pool.get()?;
+        self.set_filter(
+            Some(("active_project", project_id)),
+            &[project_id.to_string()],
+        )
+    }
+
+    /// Fails for a group without projects, which would filter nothing and show every project
+    pub fn insert_active_group(&self, name: &str, project_ids: &[String]) -> Result<()> {
+        if project_ids.is_empty() {
+            return Err(eyre!("Group '{name}' has no projects"));
+        }
+        self.set_filter(Some(("active_group", name)), project_ids)
+    }
+
+    /// The project ids which are neither in the project table nor in any entry
+    pub fn unknown_projects(&self, project_ids: &[String]) -> Result<Vec<String>> {
+        let conn = self.pool.get()?;
         let mut stmt = conn.prepare(
             r#"
-            INSERT INTO config 
-            (key, value) VALUES('active_project', $1)
-            ON CONFLICT(key) DO UPDATE SET value = ($1);
+            SELECT EXISTS (SELECT 1 FROM project WHERE project_id = ?1)
+                OR EXISTS (SELECT 1 FROM entry WHERE project_id = ?1)
             "#,
         )?;
+        let mut unknown = vec![];
+        for project_id in project_ids {
+            if !stmt.query_row([project_id], |row| row.get::<_, bool>(0))? {
+                unknown.push(project_id.clone());
+            }
+        }
+        Ok(unknown)
+    }
+
+    /// Stores the active filter and the project ids the overviews are limited to
+    fn set_filter(&self, setting: Option<(&str, &str)>, project_ids: &[String]) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM config WHERE key IN ('active_project', 'active_group')",
+            [],
+        )?;
+        tx.execute("DELETE FROM filter_project", [])?;
+        if let Some((key, value)) = setting {
+            tx.execute(
+                "INSERT INTO config (key, value) VALUES (?1, ?2)",
+                (key, value),
+            )?;
+        }
+        for project_id in project_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO filter_project (project_id) VALUES (?1)",
+                [project_id],
+            )?;
+        }
+        tx.commit()?;
 
-        let result = stmt.execute([&(project_id)])?;
         Ok(())
     }
 
-    pub fn insert(&self, mut items: Vec<TimetEntry>) -> Result<()> {
+    pub fn insert(&self, items: Vec<TimetEntry>) -> Result<()> {
         let conn = &self.pool.get()?;
-        let mut stmt = conn.prepare(
-            r#"
-                INSERT INTO entry ( 
-                    date,                   
-                    hours, 
-                    project_name,
-                    project_id
-                )
-                VALUES (?1, ?2, ?3, ?4)
-                "#,
+        insert_entries(conn, items)
+    }
+
+    /// Replaces the entries of the month with the entries fetched from the API
+    pub fn replace_month(&self, year: i32, month: u32, items: Vec<TimetEntry>) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM entry WHERE strftime('%Y-%m', date) = ?1",
+            [format!("{year:04}-{month:02}")],
         )?;
+        insert_entries(&tx, items)?;
+        tx.commit()?;
+
+        Ok(())
+    }
 
-        for item in items {
-            let date = match chrono::NaiveDate::from_yo_opt(item.year, item.day_of_year) {
-                Some(m) => m,
-                None => {
-                    return Err(eyre!(
-                        "Could not create date from {}-{}",
-                        item.year,
-                        item.day_of_year
-                    ))
-                }
-            };
-            stmt.execute((&date, &item.hours, &item.project_name, &item.project_id))?;
+    /// Replaces the project catalogue with the projects fetched from the API
+    pub fn insert_projects(&self, projects: Vec<TimetProject>) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM project", [])?;
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                INSERT INTO project (project_id, project_name, archived)
+                VALUES (?1, ?2, ?3)
+                "#,
+            )?;
+            for project in projects {
+                stmt.execute((&project.project_id, &project.project_name, project.archived))?;
+            }
         }
+        tx.commit()?;
 
         Ok(())
     }
@@ -162,11 +335,139 @@ impl Store {
         Ok(())
     }
 
+    /// Hours logged for the project on the given date, if any
+    pub fn get_hours(&self, project_id: &str, date: &NaiveDate) -> Result<Option<f32>> {
+        let conn = self.pool.get()?;
+        let mut stmt =
+            conn.prepare("SELECT hours FROM entry WHERE project_id = ?1 AND date = ?2")?;
+
+        stmt.query_row((project_id, date), |row| row.get(0))
+            .optional()
+            .map_err(|err| color_eyre::Report::new(err))
+    }
+
+    /// Records a change so it can be undone
+    pub fn insert_journal(
+        &self,
+        project_id: &str,
+        date: &NaiveDate,
+        previous_hours: Option<f32>,
+        hours: f32,
+    ) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            r#"
+            INSERT INTO journal (created, date, project_id, previous_hours, hours)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            (chrono::Utc::now(), date, project_id, previous_hours, hours),
+        )?;
+        Ok(())
+    }
+
+    /// The most recent change which has not been undone
+    pub fn last_journal(&self) -> Result<Option<JournalEntry>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT j.id, j.date, j.project_id, COALESCE(p.project_name, j.project_id),
+                   j.previous_hours, j.hours
+            FROM journal j
+            LEFT JOIN project p ON p.project_id = j.project_id
+            WHERE j.undone = 0
+            ORDER BY j.id DESC
+            LIMIT 1
+            "#,
+        )?;
+
+        stmt.query_row([], |row| {
+            Ok(JournalEntry {
+                id: row.get(0)?,
+                date: row.get(1)?,
+                project_id: row.get(2)?,
+                project_name: row.get(3)?,
+                previous_hours: row.get(4)?,
+                hours: row.get(5)?,
+            })
+        })
+        .optional()
+        .map_err(|err| color_eyre::Report::new(err))
+    }
+
+    pub fn mark_undone(&self, id: i64) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute("UPDATE journal SET undone = 1 WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Appends a call to post hours to the submission log, keeping the start of the response
+    pub fn insert_submission(
+        &self,
+        hours: &Hours,
+        source: &str,
+        note: Option<&str>,
+        status_code: Option<i32>,
+        response: &str,
+    ) -> Result<()> {
+        let snippet: String = response.chars().take(RESPONSE_SNIPPET_LENGTH).collect();
+        let conn = self.pool.get()?;
+        conn.execute(
+            r#"
+            INSERT INTO submission_log (submitted, date, project_id, hours, source, note, status_code, response)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+            (
+                chrono::Utc::now(),
+                hours.date,
+                hours.project,
+                hours.hours,
+                source,
+                note,
+                status_code,
+                snippet,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// The most recent submissions, newest first
+    pub fn submissions(&self, limit: usize) -> Result<Vec<Submission>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT s.submitted, s.date, s.project_id, COALESCE(p.project_name, s.project_id),
+                   s.hours, s.source, s.note, s.status_code, s.response
+            FROM submission_log s
+            LEFT JOIN project p ON p.project_id = s.project_id
+            ORDER BY s.id DESC
+            LIMIT ?1
+            "#,
+        )?;
+
+        let rows = stmt.query_map([limit], |row| {
+            Ok(Submission {
+                submitted: row.get(0)?,
+                date: row.get(1)?,
+                project_id: row.get(2)?,
+                project_name: row.get(3)?,
+                hours: row.get(4)?,
+                source: row.get(5)?,
+                note: row.get(6)?,
+                status_code: row.get(7)?,
+                response: row.get(8)?,
+            })
+        })?;
+
+        rows.map(|result| result.map_err(|err| color_eyre::Report::new(err)))
+            .collect()
+    }
+
     #[allow(clippy::let_and_return)]
     pub fn projects(&self) -> Result<Vec<Project>> {
         let conn = self.pool.get()?;
-        let mut stmt = conn
-            .prepare("select DISTINCT project_id, project_name FROM entry WHERE hours IS NOT 0 ORDER BY project_name")?;
+        let mut stmt = conn.prepare(&format!(
+            "{CATALOGUE} SELECT project_id, project_name FROM catalogue WHERE archived = 0 ORDER BY project_name"
+        ))?;
 
         let result = stmt
             .query_map([], |row| {
@@ -180,9 +481,56 @@ impl Store {
         result
     }
 
+    /// Active projects with hours logged in the given month and the last day hours were logged
+    pub fn project_usage(&self, month: u32, year: i32) -> Result<Vec<ProjectUsage>> {
+        let target_date = match NaiveDate::from_ymd_opt(year, month, 1) {
+            Some(m) => m,
+            None => return Err(eyre!("Could not create date from {year}-{month}-1")),
+        };
+
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            {CATALOGUE}
+            SELECT
+                p.project_id,
+                p.project_name,
+                COALESCE(SUM(CASE WHEN strftime('%Y-%m', e.date) = ?1 THEN e.hours END), 0),
+                MAX(CASE WHEN e.hours > 0 THEN e.date END)
+            FROM catalogue p
+            LEFT JOIN entry e ON e.project_id = p.project_id
+            WHERE p.archived = 0
+            GROUP BY p.project_id, p.project_name
+            ORDER BY p.project_name
+            "#
+        ))?;
+
+        let result = stmt
+            .query_map([target_date.format("%Y-%m").to_string()], |row| {
+                Ok(ProjectUsage {
+                    project: Project {
+                        project_id: row.get(0)?,
+                        project_name: row.get(1)?,
+                    },
+                    month_hours: row.get(2)?,
+                    last_used: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<ProjectUsage>, _>>()?;
+
+        Ok(result)
+    }
+
     pub fn get_project_name(&self, project_id: &str) -> Result<String> {
         let conn = self.pool.get()?;
-        let mut stmt = conn.prepare("SELECT project_name FROM entry WHERE project_id = ?")?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT project_name FROM project WHERE project_id = ?1
+            UNION ALL
+            SELECT project_name FROM entry WHERE project_id = ?1
+            LIMIT 1
+            "#,
+        )?;
 
         let result: String = stmt.query_row([project_id], |row| row.get(0))?;
 
@@ -217,6 +565,64 @@ impl Store {
         result
     }
 
+    /// Entries with hours between `from` and `to` (inclusive) for all projects
+    pub fn get_entries(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DayEntry>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            r#"SELECT
+            id,
+            date,
+            hours,
+            project_name,
+            project_id
+            FROM entry
+            WHERE date BETWEEN ?1 AND ?2
+            AND hours IS NOT 0
+            ORDER BY project_name, date
+            "#,
+        )?;
+        let result = stmt
+            .query_map((from, to), |row| {
+                Ok(DayEntry {
+                    id: row.get(0)?,
+                    date: row.get(1)?,
+                    hours: row.get(2)?,
+                    project_name: row.get(3)?,
+                    project_id: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<DayEntry>, _>>()?;
+
+        Ok(result)
+    }
+
+    /// Hides a suggested recurring entry
+    pub fn insert_dismissed(&self, date: &NaiveDate, project_id: &str) -> Result<()> {
+        let conn = self.pool.get()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO dismissed_suggestion (date, project_id) VALUES (?1, ?2)",
+            (date, project_id),
+        )?;
+        Ok(())
+    }
+
+    /// Dismissed suggestions between `from` and `to` (inclusive)
+    pub fn get_dismissed(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, String)>> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT date, project_id FROM dismissed_suggestion WHERE date BETWEEN ?1 AND ?2",
+        )?;
+        let result = stmt
+            .query_map((from, to), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(NaiveDate, String)>, _>>()?;
+
+        Ok(result)
+    }
+
     pub fn get_yearly_overview(&self, year: i32) -> Result<Vec<Year>> {
         let conn = &self.pool.get()?;
         let mut stmt = conn.prepare(
@@ -231,16 +637,16 @@ impl Store {
             COALESCE(SUM(e.hours), 0) AS total_hour
         FROM 
             months
-        LEFT JOIN entry e ON strftime('%Y', e.date) = strftime('%Y', 'now')
+        LEFT JOIN entry e ON strftime('%Y', e.date) = ?1
             AND strftime('%m', e.date) = printf('%02d', m)
-            AND (project_id = (SELECT value FROM config WHERE key = 'active_project')
-                 OR NOT EXISTS (SELECT 1 FROM config WHERE key = 'active_project'))
+            AND (project_id IN (SELECT project_id FROM filter_project)
+                 OR NOT EXISTS (SELECT 1 FROM filter_project))
         GROUP BY m
         ORDER BY m;
         "#,
         )?;
         let data = stmt
-            .query_map([], |row| {
+            .query_map([format!("{year:04}")], |row| {
                 let month: u32 = row.get::<_, String>(0)?.parse().unwrap();
                 let total_hours: f32 = row.get(1)?;
                 let month_date = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
@@ -268,11 +674,11 @@ impl Store {
         let conn = self.pool.get()?;
         let mut stmt = conn.prepare(
             r#"
-            SELECT date, project_name, hours 
+            SELECT date, project_name, project_id, hours 
             FROM entry 
             WHERE date LIKE ?1 
-            AND (project_id = (SELECT value FROM config WHERE key = 'active_project')
-                 OR NOT EXISTS (SELECT 1 FROM config WHERE key = 'active_project'))
+            AND (project_id IN (SELECT project_id FROM filter_project)
+                 OR NOT EXISTS (SELECT 1 FROM filter_project))
             AND hours IS NOT 0 
             ORDER BY date ASC
             "#,
@@ -282,7 +688,8 @@ impl Store {
                 Ok(Month {
                     date: row.get(0)?,
                     project_name: row.get(1)?,
-                    hours: row.get(2)?,
+                    project_id: row.get(2)?,
+                    hours: row.get(3)?,
                 })
             })?
             .map(|result| result.map_err(|err| color_eyre::Report::new(err)))
@@ -292,6 +699,36 @@ impl Store {
     }
 }
 
+fn insert_entries(conn: &Connection, items: Vec<TimetEntry>) -> Result<()> {
+    let mut stmt = conn.prepare(
+        r#"
+            INSERT INTO entry ( 
+                date,                   
+                hours, 
+                project_name,
+                project_id
+            )
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+    )?;
+
+    for item in items {
+        let date = match chrono::NaiveDate::from_yo_opt(item.year, item.day_of_year) {
+            Some(m) => m,
+            None => {
+                return Err(eyre!(
+                    "Could not create date from {}-{}",
+                    item.year,
+                    item.day_of_year
+                ))
+            }
+        };
+        stmt.execute((&date, &item.hours, &item.project_name, &item.project_id))?;
+    }
+
+    Ok(())
+}
+
 /// EntryContainer is a simple generic container. Perhaps a bad idea?
 pub struct EntryContainer<T> {
     begin: NaiveDate,
@@ -308,18 +745,91 @@ pub struct DayEntry {
     pub project_id: String,
 }
 
+/// Number of characters kept from the response to a submission
+const RESPONSE_SNIPPET_LENGTH: usize = 200;
+
+/// A call to post hours as recorded in the submission log
+#[derive(Debug, Clone, PartialEq)]
+pub struct Submission {
+    pub submitted: chrono::DateTime<chrono::Utc>,
+    pub date: NaiveDate,
+    pub project_id: String,
+    pub project_name: String,
+    pub hours: f32,
+    /// Where the submission came from, e.g. the timesheet
+    pub source: String,
+    /// Why the hours were submitted, e.g. the hours an undo replaced
+    pub note: Option<String>,
+    /// Missing when no response was received
+    pub status_code: Option<i32>,
+    pub response: String,
+}
+
+/// A change of hours for a project on a day, with the value it replaced
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub project_id: String,
+    pub project_name: String,
+    pub previous_hours: Option<f32>,
+    pub hours: f32,
+}
+
 #[derive(Debug)]
 pub struct Entry {
     pub date: NaiveDate,
     pub hours: f32,
     pub project_name: String,
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Project {
     pub project_id: String,
     pub project_name: String,
 }
 
+/// Limits the overviews to a single project or a named group of projects
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Project(Project),
+    Group {
+        name: String,
+        project_ids: Vec<String>,
+    },
+}
+
+impl Filter {
+    pub fn name(&self) -> &str {
+        match self {
+            Filter::Project(p) => &p.project_name,
+            Filter::Group { name, .. } => name,
+        }
+    }
+
+    /// The project to log hours to by default, groups have none
+    pub fn project(&self) -> Option<&Project> {
+        match self {
+            Filter::Project(p) => Some(p),
+            Filter::Group { .. } => None,
+        }
+    }
+
+    pub fn contains(&self, project_id: &str) -> bool {
+        match self {
+            Filter::Project(p) => p.project_id == project_id,
+            Filter::Group { project_ids, .. } => project_ids.iter().any(|id| id == project_id),
+        }
+    }
+}
+
+/// A project together with how much and how recently it has been used
+#[derive(Debug, Clone)]
+pub struct ProjectUsage {
+    pub project: Project,
+    pub month_hours: f32,
+    pub last_used: Option<NaiveDate>,
+}
+
 #[derive(Debug)]
 pub struct Year {
     pub hours: f32,
@@ -345,6 +855,7 @@ pub struct OverviewContent {
 pub struct Month {
     pub date: NaiveDate,
     pub project_name: String,
+    pub project_id: String,
     pub hours: f32,
 }
 
@@ -354,7 +865,7 @@ mod tests {
     use r2d2_sqlite::SqliteConnectionManager;
     use rusqlite::Connection;
 
-    use crate::api::{TimetEntries, TimetEntry};
+    use crate::api::{Hours, TimetEntries, TimetEntry, TimetProject, TimetProjects};
     use crate::store::Store;
 
     fn create_timet_entries() -> Vec<TimetEntry> {
@@ -364,6 +875,13 @@ mod tests {
         entries.entries.unwrap()
     }
 
+    fn create_timet_projects() -> Vec<TimetProject> {
+        let input = std::fs::read("testdata/projects.json").unwrap();
+        let projects: TimetProjects = serde_json::from_slice(&input).unwrap();
+
+        projects.projects.unwrap()
+    }
+
     fn create_store() -> Store {
         let manager = SqliteConnectionManager::memory();
         let pool = r2d2::Pool::new(manager).unwrap();
@@ -378,7 +896,7 @@ mod tests {
         store.insert(items).unwrap();
 
         let sum = store.entry_count().unwrap();
-        assert_eq!(sum > 0, true);
+        assert!(sum > 0);
     }
 
     #[test]
@@ -389,7 +907,7 @@ mod tests {
         store.insert(items).unwrap();
 
         let mut sum = store.entry_count().unwrap();
-        assert_eq!(&sum > &0, true);
+        assert!(sum > 0);
         store.entry_truncate().unwrap();
         sum = store.entry_count().unwrap();
         assert_eq!(sum, 0);
@@ -400,10 +918,19 @@ mod tests {
         let mut store = create_store();
         store.create_db().unwrap();
         let insert = store.insert_active_project("foobar");
-        assert_eq!(insert.is_ok(), true);
+        assert!(insert.is_ok());
 
         let result = store.default_project();
         assert!(result.is_ok());
+
+        store.insert_projects(create_timet_projects()).unwrap();
+        store.insert_active_project("Qk3vT0pZr8LmN2xYc7Wd").unwrap();
+        let project = store.default_project().unwrap();
+        assert_eq!(
+            project.map(|p| p.project_name),
+            Some("Project N".to_string()),
+            "projects without logged hours can be active"
+        );
     }
 
     #[test]
@@ -412,7 +939,7 @@ mod tests {
         store.create_db().unwrap();
         let items = create_timet_entries();
         let result = store.insert(items);
-        assert_eq!(result.is_ok(), true)
+        assert!(result.is_ok())
     }
 
     #[test]
@@ -421,36 +948,116 @@ mod tests {
         store.create_db().unwrap();
         let items = create_timet_entries();
         store.insert(items.clone()).unwrap();
-        let project = items.get(0).unwrap();
+        let project = items.first().unwrap();
         let result =
             store.insert_hours(&project.project_id, &7.5, &chrono::Utc::now().date_naive());
-        assert_eq!(result.is_ok(), true, "{:?}", &result)
+        assert!(result.is_ok(), "{:?}", &result)
     }
 
     #[test]
     fn test_store_projects() {
         let mut store = create_store();
         store.create_db().unwrap();
-        let items = create_timet_entries();
-        store.insert(items).unwrap();
+        store.insert_projects(create_timet_projects()).unwrap();
 
         let projects = store.projects();
         assert!(projects.is_ok());
+        let projects = projects.unwrap();
+        assert!(!projects.is_empty(), "should always return test projects");
+        assert!(
+            projects.iter().any(|p| p.project_name == "Project N"),
+            "projects without entries should be listed"
+        );
+        assert!(
+            !projects.iter().any(|p| p.project_name == "Project Z"),
+            "archived projects should not be listed"
+        );
+
+        store.insert_projects(create_timet_projects()).unwrap();
+        assert_eq!(store.projects().unwrap().len(), projects.len());
+    }
+
+    #[test]
+    fn test_store_projects_before_refresh() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+        store.insert(create_timet_entries()).unwrap();
+        store.insert_active_project("mBED9hi2XFs51XzeH0hD").unwrap();
+
+        let projects = store.projects().unwrap();
+        assert!(
+            projects.iter().any(|p| p.project_name == "Project A"),
+            "falls back to logged entries until the catalogue is fetched"
+        );
+        assert_eq!(
+            store.default_project().unwrap().map(|p| p.project_name),
+            Some("Project A".to_string())
+        );
+        assert_eq!(store.project_usage(4, 2024).unwrap().len(), projects.len());
+
+        store.insert_projects(create_timet_projects()).unwrap();
         assert!(
-            !projects.unwrap().is_empty(),
-            "should always return test projects"
+            store
+                .projects()
+                .unwrap()
+                .iter()
+                .any(|p| p.project_name == "Project N"),
+            "the catalogue replaces the fallback"
         );
     }
 
+    #[test]
+    fn test_store_project_usage() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+        store.insert(create_timet_entries()).unwrap();
+        store.insert_projects(create_timet_projects()).unwrap();
+
+        let usage = store.project_usage(4, 2024).unwrap();
+        assert_eq!(usage.len(), 3, "archived projects are excluded");
+
+        let project_a = usage
+            .iter()
+            .find(|u| u.project.project_name == "Project A")
+            .unwrap();
+        assert!(project_a.month_hours > 0.0);
+        assert!(project_a.last_used.is_some());
+
+        let project_n = usage
+            .iter()
+            .find(|u| u.project.project_name == "Project N")
+            .unwrap();
+        assert_eq!(project_n.month_hours, 0.0);
+        assert!(project_n.last_used.is_none());
+
+        let next_month = store.project_usage(5, 2024).unwrap();
+        assert!(next_month.iter().all(|u| u.month_hours == 0.0));
+    }
+
+    #[test]
+    fn test_store_get_project_name() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+        store.insert(create_timet_entries()).unwrap();
+
+        let name = store.get_project_name("mBED9hi2XFs51XzeH0hD").unwrap();
+        assert_eq!(name, "Project A", "falls back to logged entries");
+
+        store.insert_projects(create_timet_projects()).unwrap();
+        let name = store.get_project_name("Qk3vT0pZr8LmN2xYc7Wd").unwrap();
+        assert_eq!(name, "Project N");
+    }
+
     #[test]
     fn test_store_get_yearly_overview() {
         let mut store = create_store();
         store.create_db().unwrap();
         let items = create_timet_entries();
         store.insert(items).unwrap();
-        let result = store.get_yearly_overview(2024);
-        dbg!(&result);
-        assert_eq!(result.is_ok(), true);
+        let result = store.get_yearly_overview(2024).unwrap();
+        assert_eq!(result.len(), 12);
+        assert!(result[3].hours > 0.0, "April 2024 has entries");
+        assert!(store.get_yearly_overview(2023).unwrap()[3].hours == 0.0);
     }
     #[test]
     fn test_store_get_month() {
@@ -460,10 +1067,182 @@ mod tests {
         store.insert(items).unwrap();
 
         let result = store.get_month_overview(4, 2024);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
         assert!(!result.unwrap().is_empty());
     }
 
+    #[test]
+    fn test_store_active_group() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+        store.insert(create_timet_entries()).unwrap();
+        let all: f32 = store
+            .get_month_overview(4, 2024)
+            .unwrap()
+            .iter()
+            .map(|m| m.hours)
+            .sum();
+
+        store
+            .insert_active_group("Group", &["8oWGyEBxVEyvGw3GZSdp".to_string()])
+            .unwrap();
+        assert_eq!(store.active_group().unwrap(), Some("Group".to_string()));
+        let month = store.get_month_overview(4, 2024).unwrap();
+        assert!(!month.is_empty());
+        assert!(month.iter().all(|m| m.project_name == "Project G"));
+
+        store
+            .insert_active_group(
+                "Group",
+                &[
+                    "8oWGyEBxVEyvGw3GZSdp".to_string(),
+                    "mBED9hi2XFs51XzeH0hD".to_string(),
+                ],
+            )
+            .unwrap();
+        let grouped: f32 = store
+            .get_month_overview(4, 2024)
+            .unwrap()
+            .iter()
+            .map(|m| m.hours)
+            .sum();
+        assert_eq!(grouped, all, "group aggregates all its projects");
+
+        store.insert_active_project("mBED9hi2XFs51XzeH0hD").unwrap();
+        assert_eq!(store.active_group().unwrap(), None);
+
+        store.delete_active_filter().unwrap();
+        assert_eq!(store.active_group().unwrap(), None);
+        assert!(store.default_project().unwrap().is_none());
+
+        assert!(store.insert_active_group("Empty", &[]).is_err());
+        assert_eq!(
+            store
+                .unknown_projects(&["8oWGyEBxVEyvGw3GZSdp".to_string(), "gone".to_string()])
+                .unwrap(),
+            vec!["gone".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_store_migrate_active_project() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+        store.insert(create_timet_entries()).unwrap();
+        // a database written before the filter was stored as project ids
+        let conn = store.pool.get().unwrap();
+        conn.execute(
+            "INSERT INTO config (key, value) VALUES ('active_project', '8oWGyEBxVEyvGw3GZSdp')",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        store.create_db().unwrap();
+        let month = store.get_month_overview(4, 2024).unwrap();
+        assert!(!month.is_empty());
+        assert!(month.iter().all(|m| m.project_name == "Project G"));
+
+        store.delete_active_filter().unwrap();
+        store.create_db().unwrap();
+        assert!(store.get_month_overview(4, 2024).unwrap().len() > month.len());
+    }
+
+    #[test]
+    fn test_store_get_entries() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+        store.insert(create_timet_entries()).unwrap();
+
+        let from = chrono::NaiveDate::from_ymd_opt(2024, 4, 22).unwrap();
+        let to = chrono::NaiveDate::from_ymd_opt(2024, 4, 28).unwrap();
+        let entries = store.get_entries(from, to).unwrap();
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|e| e.date >= from && e.date <= to));
+    }
+
+    #[test]
+    fn test_store_dismissed() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 4, 22).unwrap();
+        store.insert_dismissed(&date, "foobar").unwrap();
+        store.insert_dismissed(&date, "foobar").unwrap();
+
+        let dismissed = store.get_dismissed(date, date).unwrap();
+        assert_eq!(dismissed, vec![(date, "foobar".to_string())]);
+        assert!(store
+            .get_dismissed(date.succ_opt().unwrap(), date.succ_opt().unwrap())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_store_journal() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+        store.insert_projects(create_timet_projects()).unwrap();
+        assert!(store.last_journal().unwrap().is_none());
+
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 4, 22).unwrap();
+        assert!(store
+            .get_hours("mBED9hi2XFs51XzeH0hD", &date)
+            .unwrap()
+            .is_none());
+        store
+            .insert_journal("mBED9hi2XFs51XzeH0hD", &date, None, 7.5)
+            .unwrap();
+        store
+            .insert_journal("mBED9hi2XFs51XzeH0hD", &date, Some(7.5), 4.0)
+            .unwrap();
+
+        let last = store.last_journal().unwrap().unwrap();
+        assert_eq!(last.project_name, "Project A");
+        assert_eq!(last.previous_hours, Some(7.5));
+        assert_eq!(last.hours, 4.0);
+
+        store.mark_undone(last.id).unwrap();
+        let last = store.last_journal().unwrap().unwrap();
+        assert_eq!(last.previous_hours, None);
+        assert_eq!(last.hours, 7.5);
+    }
+
+    #[test]
+    fn test_store_submissions() {
+        let mut store = create_store();
+        store.create_db().unwrap();
+        store.insert_projects(create_timet_projects()).unwrap();
+
+        let hours = Hours {
+            project: "mBED9hi2XFs51XzeH0hD",
+            date: chrono::NaiveDate::from_ymd_opt(2024, 4, 22).unwrap(),
+            hours: 7.5,
+        };
+        store
+            .insert_submission(&hours, "log hours", None, Some(500), &"x".repeat(1000))
+            .unwrap();
+        store
+            .insert_submission(&hours, "undo", Some("replaces 8.0h"), None, "timeout")
+            .unwrap();
+
+        let submissions = store.submissions(10).unwrap();
+        assert_eq!(submissions.len(), 2);
+        assert_eq!(submissions[0].source, "undo", "newest first");
+        assert_eq!(submissions[0].note.as_deref(), Some("replaces 8.0h"));
+        assert_eq!(submissions[1].note, None);
+        assert_eq!(submissions[0].status_code, None);
+        assert_eq!(submissions[1].project_name, "Project A");
+        assert_eq!(submissions[1].status_code, Some(500));
+        assert_eq!(submissions[1].response.len(), 200);
+
+        let conn = store.pool.get().unwrap();
+        assert!(conn.execute("DELETE FROM submission_log", []).is_err());
+        assert!(conn
+            .execute("UPDATE submission_log SET hours = 0", [])
+            .is_err());
+    }
+
     #[test]
     fn test_store_get_days() {
         let mut store = create_store();
@@ -472,9 +1251,9 @@ mod tests {
         store.insert(items).unwrap();
 
         let result = store.get_days();
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
         let days = result.unwrap();
-        assert_eq!(days.is_empty(), false);
+        assert!(!days.is_empty());
     }
 
     #[test]
@@ -0,0 +1,440 @@
+//! Refreshes the store from the API in the background, fetching months with a bounded pool of workers
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use color_eyre::eyre::eyre;
+use log::error;
+
+use crate::api::{self, Api};
+use crate::model::Message;
+use crate::store::Store;
+use crate::task::{self, panic_message, Task, TaskFailure};
+
+/// Months fetched at the same time
+pub const WORKERS: usize = 4;
+
+/// Numbers every refresh of the process, also across profile switches, so the late messages
+/// of a cancelled refresh are not taken for those of the next one
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Progress of a month while syncing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonthStatus {
+    Pending,
+    Synced,
+    Failed(String),
+}
+
+/// How a refresh was started, which decides where its progress is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refresh {
+    /// Started by the user, showing its progress over the whole screen
+    Manual,
+    /// Started on `refresh_interval`, only showing a spinner in the header
+    Background,
+}
+
+/// A refresh in progress, which stops taking new months once cancelled
+#[derive(Debug)]
+pub struct Refreshing {
+    pub refresh: Refresh,
+    /// Sent with every message of the refresh
+    pub generation: u64,
+    /// Status of every month from January up to the current month
+    pub months: Vec<MonthStatus>,
+    cancelled: Arc<AtomicBool>,
+    /// The thread fetching the months, watched in case it dies without a word
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Refreshing {
+    /// Fetches the projects, then the months of the year up to `months`.
+    /// Failing to fetch the projects is only logged, the store keeps the projects it has.
+    /// Every month is stored as soon as it arrives, so failed months keep their previous entries.
+    pub fn start(
+        refresh: Refresh,
+        api: Arc<Api>,
+        store: Arc<Store>,
+        sender: Sender<Message>,
+        year: i32,
+        months: u32,
+    ) -> Self {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let generation = GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut refreshing = Refreshing {
+            refresh,
+            generation,
+            months: vec![MonthStatus::Pending; months as usize],
+            cancelled: cancelled.clone(),
+            handle: None,
+        };
+
+        let handle = task::spawn(Task::Refresh(generation), sender.clone(), move || {
+            // the catalogue only names projects, the entries are synced without it
+            if let Err(err) = api
+                .get_projects()
+                .and_then(|projects| store.insert_projects(projects))
+            {
+                error!("Fetching projects: {err:?}");
+            }
+            if cancelled.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+
+            let queue = Arc::new(Mutex::new((1..=months).collect::<VecDeque<u32>>()));
+            let workers: Vec<_> = (0..WORKERS.min(months as usize))
+                .map(|_| {
+                    let (api, store, sender) = (api.clone(), store.clone(), sender.clone());
+                    let (queue, cancelled) = (queue.clone(), cancelled.clone());
+                    thread::spawn(move || loop {
+                        if cancelled.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        let Some(month) = queue.lock().unwrap().pop_front() else {
+                            return;
+                        };
+                        // a month which panics fails like any other month
+                        let result = task::catch(|| {
+                            let entries = api.get_month(year as u32, month)?;
+                            store.replace_month(year, month, entries)
+                        });
+                        let error = result.err().map(|err| {
+                            error!("{err:?}");
+                            api::describe(&err)
+                        });
+                        if cancelled.load(Ordering::Relaxed)
+                            || sender
+                                .send(Message::RefreshMonth(generation, month, error))
+                                .is_err()
+                        {
+                            return;
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                worker.join().map_err(|panic| {
+                    eyre!("A worker crashed: {}", panic_message(panic.as_ref()))
+                })?;
+            }
+
+            if !cancelled.load(Ordering::Relaxed) {
+                let _ = sender.send(Message::RefreshCompleted(generation));
+            }
+            Ok(())
+        });
+        refreshing.handle = Some(handle);
+
+        refreshing
+    }
+
+    /// Stops fetching, months already fetched are kept
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Notices the thread ending without reporting how the refresh went, which only happens
+    /// when it panicked outside of the work it was given
+    pub fn watch(&mut self) -> Option<TaskFailure> {
+        if !self.handle.as_ref().is_some_and(JoinHandle::is_finished) {
+            return None;
+        }
+        let panic = self.handle.take()?.join().err()?;
+        Some(TaskFailure {
+            task: Task::Refresh(self.generation),
+            error: panic_message(panic.as_ref()),
+            panicked: true,
+        })
+    }
+
+    pub fn set(&mut self, month: u32, status: MonthStatus) {
+        if let Some(m) = self.months.get_mut(month as usize - 1) {
+            *m = status;
+        }
+    }
+
+    /// Months which are no longer pending
+    pub fn done(&self) -> usize {
+        self.months
+            .iter()
+            .filter(|m| **m != MonthStatus::Pending)
+            .count()
+    }
+
+    pub fn synced(&self) -> usize {
+        self.months
+            .iter()
+            .filter(|m| **m == MonthStatus::Synced)
+            .count()
+    }
+
+    /// Names the months which failed with the first error, if any failed
+    pub fn failures(&self) -> Option<String> {
+        let failed: Vec<(u32, &str)> = self
+            .months
+            .iter()
+            .zip(1..)
+            .filter_map(|(status, month)| match status {
+                MonthStatus::Failed(err) => Some((month, err.as_str())),
+                _ => None,
+            })
+            .collect();
+        let (_, first) = failed.first()?;
+        let names: Vec<&str> = failed.iter().map(|(month, _)| month_name(*month)).collect();
+        Some(format!("Refresh failed for {}: {first}", names.join(", ")))
+    }
+}
+
+pub fn month_name(month: u32) -> &'static str {
+    u8::try_from(month)
+        .ok()
+        .and_then(|m| chrono::Month::try_from(m).ok())
+        .map_or("?", |m| m.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crossterm::event::KeyCode;
+
+    use crate::harness::Harness;
+    use crate::keys::{Action, Context};
+    use crate::model::{ActiveView, Message};
+    use crate::store::Store;
+    use crate::sync::{MonthStatus, Refresh, Refreshing};
+    use crate::task::{Task, TaskFailure};
+
+    fn done(msg: &Message) -> bool {
+        matches!(msg, Message::RefreshCompleted(_) | Message::TaskFailed(_))
+    }
+
+    #[test]
+    fn test_refresh_keeps_synced_months() {
+        let mut harness = Harness::with_store(Store::memory().unwrap());
+        harness.api.fail("/entries-bymonth?year=2024&month=2");
+        harness.send(Message::RefreshStarted);
+        assert_eq!(
+            harness.model.refreshing.as_ref().unwrap().months,
+            vec![MonthStatus::Pending; 4]
+        );
+
+        harness.wait_for(|m| matches!(m, Message::RefreshMonth(_, 2, _)));
+        let months = &harness.model.refreshing.as_ref().unwrap().months;
+        assert!(matches!(&months[1], MonthStatus::Failed(err) if err.contains("400")));
+
+        harness.wait_for(done);
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert_eq!(harness.model.last_synced, None, "not every month synced");
+        assert!(harness.model.overview[3].hours > 0.0, "April is kept");
+        assert!(
+            harness
+                .model
+                .notice()
+                .unwrap()
+                .starts_with("Refresh failed for February: "),
+            "{:?}",
+            harness.model.notice()
+        );
+    }
+
+    #[test]
+    fn test_refresh_without_projects() {
+        let mut harness = Harness::with_store(Store::memory().unwrap());
+        harness.api.fail("/projects");
+        harness.send(Message::RefreshStarted);
+        harness.wait_for(done);
+
+        assert_eq!(harness.model.active_error_msg, None);
+        assert!(harness.model.last_synced.is_some(), "every month synced");
+        assert!(harness.model.overview[3].hours > 0.0);
+        assert!(
+            !harness.model.store.projects().unwrap().is_empty(),
+            "projects are taken from the entries"
+        );
+    }
+
+    #[test]
+    fn test_refresh_failure() {
+        let mut harness = Harness::new();
+        for month in 1..=4 {
+            harness
+                .api
+                .fail(&format!("/entries-bymonth?year=2024&month={month}"));
+        }
+        harness.send(Message::DetailMonth);
+        harness.send(Message::RefreshStarted);
+        assert_eq!(harness.model.active_view, ActiveView::Loading);
+
+        harness.wait_for(done);
+        assert!(harness.model.refreshing.is_none());
+        assert_eq!(harness.model.active_view, ActiveView::Month);
+        assert_eq!(harness.model.last_synced, None);
+        assert!(harness.model.overview[3].hours > 0.0, "the store is kept");
+        assert!(
+            harness
+                .model
+                .notice()
+                .unwrap()
+                .starts_with("Refresh failed for January, February, March, April: "),
+            "{:?}",
+            harness.model.notice()
+        );
+    }
+
+    #[test]
+    fn test_refresh_crash() {
+        let mut harness = Harness::new();
+        harness.api.delay(Duration::from_millis(100));
+        harness.send(Message::DetailMonth);
+        harness.send(Message::RefreshStarted);
+        let generation = harness.model.refreshing.as_ref().unwrap().generation;
+        harness.send(Message::TaskFailed(TaskFailure {
+            task: Task::Refresh(generation),
+            error: "A worker crashed: disk full".to_string(),
+            panicked: false,
+        }));
+
+        assert_eq!(harness.model.active_view, ActiveView::Month);
+        assert!(harness.model.refreshing.is_none());
+        assert_eq!(
+            harness.model.active_error_msg.as_deref(),
+            Some("Refresh failed: A worker crashed: disk full")
+        );
+        assert!(harness.model.overview[3].hours > 0.0, "the store is kept");
+        harness.assert_snapshot("month_error", 100, 30);
+
+        harness.key(KeyCode::Char('H'));
+        assert_eq!(harness.model.active_error_msg, None);
+    }
+
+    #[test]
+    fn test_watch() {
+        let finished = |work: fn()| {
+            let handle = thread::spawn(work);
+            while !handle.is_finished() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Refreshing {
+                refresh: Refresh::Manual,
+                generation: 1,
+                months: vec![MonthStatus::Pending],
+                cancelled: Arc::default(),
+                handle: Some(handle),
+            }
+        };
+
+        let mut refreshing = finished(|| panic!("boom"));
+        let failure = refreshing.watch().unwrap();
+        assert_eq!(failure.to_string(), "Refresh crashed: boom");
+        assert_eq!(refreshing.watch(), None, "reported once");
+
+        let mut refreshing = finished(|| {});
+        assert_eq!(refreshing.watch(), None, "ending normally is not a failure");
+    }
+
+    #[test]
+    fn test_refresh_cancel() {
+        let mut harness = Harness::with_store(Store::memory().unwrap());
+        harness.api.delay(Duration::from_millis(200));
+        harness.send(Message::RefreshStarted);
+        harness.key(KeyCode::Esc);
+
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert!(harness.model.refreshing.is_none());
+        assert_eq!(
+            harness.model.notice(),
+            Some("Refresh cancelled, kept 0 synced month(s)")
+        );
+
+        harness.drain(Duration::from_millis(500));
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert_eq!(harness.model.last_synced, None);
+        assert!(
+            harness.api.requests("GET").len() <= 1,
+            "no month is fetched after cancelling"
+        );
+    }
+
+    #[test]
+    fn test_refresh_ignores_cancelled_run() {
+        let mut harness = Harness::with_store(Store::memory().unwrap());
+        harness.api.delay(Duration::from_millis(200));
+        harness.send(Message::RefreshStarted);
+        let cancelled = harness.model.refreshing.as_ref().unwrap().generation;
+        harness.key(KeyCode::Esc);
+        harness.send(Message::RefreshStarted);
+        let running = harness.model.refreshing.as_ref().unwrap().generation;
+        assert_ne!(cancelled, running);
+
+        harness.send(Message::RefreshMonth(
+            cancelled,
+            1,
+            Some("late".to_string()),
+        ));
+        harness.send(Message::RefreshCompleted(cancelled));
+        harness.send(Message::TaskFailed(TaskFailure {
+            task: Task::Refresh(cancelled),
+            error: "A worker crashed: late".to_string(),
+            panicked: true,
+        }));
+        assert_eq!(harness.model.active_view, ActiveView::Loading);
+        assert_eq!(harness.model.active_error_msg, None);
+        assert_eq!(
+            harness.model.refreshing.as_ref().unwrap().months[0],
+            MonthStatus::Pending,
+            "the month of the cancelled run is not taken for this one"
+        );
+
+        harness.wait_for(|m| matches!(m, Message::RefreshCompleted(g) if *g == running));
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert!(harness.model.last_synced.is_some());
+    }
+
+    #[test]
+    fn test_refresh_cancel_from_palette() {
+        let mut harness = Harness::with_store(Store::memory().unwrap());
+        harness.api.delay(Duration::from_millis(200));
+        harness.send(Message::RefreshStarted);
+        harness.send(Message::Command(Context::Loading, Action::Close));
+
+        assert!(harness.model.refreshing.is_none());
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+    }
+
+    #[test]
+    fn test_refresh_keeps_view_opened_while_loading() {
+        let mut harness = Harness::with_store(Store::memory().unwrap());
+        harness.api.delay(Duration::from_millis(50));
+        harness.send(Message::RefreshStarted);
+        harness.key(KeyCode::Char('l'));
+        assert_eq!(harness.model.active_view, ActiveView::LogHours);
+
+        harness.wait_for(|m| matches!(m, Message::RefreshCompleted(_)));
+        assert!(harness.model.refreshing.is_none());
+        assert_eq!(
+            harness.model.active_view,
+            ActiveView::LogHours,
+            "the form stays open"
+        );
+    }
+
+    #[test]
+    fn test_cancel_without_refresh() {
+        let mut harness = Harness::new();
+        harness.send(Message::Command(Context::Loading, Action::Close));
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert_eq!(harness.model.notice(), None);
+
+        harness.key(KeyCode::Char('l'));
+        harness.send(Message::RefreshCancelled);
+        assert_eq!(harness.model.active_view, ActiveView::LogHours);
+        assert_eq!(harness.model.notice(), None);
+    }
+}
@@ -0,0 +1,153 @@
+//! Runs work in the background, reporting errors and panics to the event loop instead of dying silently
+
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+
+use color_eyre::{eyre::eyre, Report, Result};
+use log::error;
+
+use crate::api;
+use crate::model::Message;
+
+/// The kinds of background work, named in failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    /// Reading keys and mouse events from the terminal
+    Input,
+    /// Syncing the store with the API, with the generation of the refresh
+    Refresh(u64),
+    /// Posting hours to the API
+    Submit,
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Task::Input => "Input",
+            Task::Refresh(_) => "Refresh",
+            Task::Submit => "Submit",
+        })
+    }
+}
+
+/// A background task which ended with an error or a panic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskFailure {
+    pub task: Task,
+    pub error: String,
+    pub panicked: bool,
+}
+
+impl fmt::Display for TaskFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = if self.panicked { "crashed" } else { "failed" };
+        write!(f, "{} {outcome}: {}", self.task, self.error)
+    }
+}
+
+/// Runs the work on a thread, sending [`Message::TaskFailed`] when it returns an error or panics.
+/// Work which succeeds sends its own messages.
+pub fn spawn<F>(task: Task, sender: Sender<Message>, work: F) -> JoinHandle<()>
+where
+    F: FnOnce() -> Result<()> + Send + 'static,
+{
+    thread::spawn(move || {
+        let failure = match panic::catch_unwind(AssertUnwindSafe(work)) {
+            Ok(Ok(())) => return,
+            Ok(Err(err)) => {
+                error!("{task}: {err:?}");
+                TaskFailure {
+                    task,
+                    error: describe(&err),
+                    panicked: false,
+                }
+            }
+            Err(panic) => {
+                let message = panic_message(panic.as_ref());
+                error!("{task} panicked: {message}");
+                TaskFailure {
+                    task,
+                    error: message,
+                    panicked: true,
+                }
+            }
+        };
+        if sender.send(Message::TaskFailed(failure)).is_err() {
+            error!("{task}: the event loop is gone");
+        }
+    })
+}
+
+/// Explains the error to the user, after the context it was wrapped in, e.g. `Fetching projects: ...`
+fn describe(err: &Report) -> String {
+    match err.chain().count() {
+        1 => api::describe(err),
+        _ => format!("{err}: {}", api::describe(err)),
+    }
+}
+
+/// Runs the work, turning a panic into an error
+pub fn catch<T>(work: impl FnOnce() -> Result<T>) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(work))
+        .unwrap_or_else(|panic| Err(eyre!("panicked: {}", panic_message(panic.as_ref()))))
+}
+
+/// The message given to `panic!`, if it was a string
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use color_eyre::eyre::{eyre, WrapErr};
+
+    use crate::model::Message;
+    use crate::task::{catch, spawn, Task, TaskFailure};
+
+    fn run(work: impl FnOnce() -> color_eyre::Result<()> + Send + 'static) -> TaskFailure {
+        let (sender, receiver) = mpsc::channel();
+        spawn(Task::Refresh(1), sender, work).join().unwrap();
+        match receiver.recv_timeout(Duration::from_secs(1)) {
+            Ok(Message::TaskFailed(failure)) => failure,
+            other => panic!("expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_task_error() {
+        let failure = run(|| Err(eyre!("disk full")));
+        assert_eq!(failure.to_string(), "Refresh failed: disk full");
+
+        let failure = run(|| Err(eyre!("disk full")).wrap_err("Storing April"));
+        assert_eq!(failure.error, "Storing April: disk full");
+    }
+
+    #[test]
+    fn test_task_panic() {
+        let failure = run(|| panic!("index out of bounds"));
+        assert!(failure.panicked);
+        assert_eq!(failure.to_string(), "Refresh crashed: index out of bounds");
+
+        let (sender, receiver) = mpsc::channel();
+        spawn(Task::Refresh(1), sender, || Ok(())).join().unwrap();
+        assert!(receiver.try_recv().is_err(), "success is not reported");
+    }
+
+    #[test]
+    fn test_catch() {
+        let n = 3;
+        let err = catch::<()>(|| panic!("bad {n}")).unwrap_err();
+        assert_eq!(err.to_string(), "panicked: bad 3");
+        assert_eq!(catch(|| Ok(1)).unwrap(), 1);
+    }
+}
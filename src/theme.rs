@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+
+use ratatui::style::palette::tailwind;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ThemeError {
+    #[error("Invalid colour '{color}' for {element} in [theme.styles]")]
+    InvalidColor { element: String, color: String },
+}
+
+/// The built-in themes
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+/// The parts of the interface which can be styled
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Element {
+    Popup,
+    Border,
+    Selected,
+    Row,
+    RowAlt,
+    Key,
+    Success,
+    Error,
+    Changed,
+    Hint,
+}
+
+/// Replaces the colours of an element, colours are names (`red`), indexes (`42`) or hex (`#ff0000`)
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StyleConfig {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: Option<bool>,
+}
+
+/// The `[theme]` section
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub name: ThemeName,
+    #[serde(default)]
+    pub styles: BTreeMap<Element, StyleConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub popup: Style,
+    pub border: Style,
+    pub selected: Style,
+    /// Background of even rows, odd rows use `row_alt`
+    pub row: Style,
+    pub row_alt: Style,
+    /// Keys in the help
+    pub key: Style,
+    pub success: Style,
+    pub error: Style,
+    /// Values which have been changed but not saved
+    pub changed: Style,
+    /// Notes below forms
+    pub hint: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(&ThemeConfig::default()).expect("the default theme is valid")
+    }
+}
+
+impl Theme {
+    /// The configured theme with its overrides, without colours when `NO_COLOR` is set
+    pub fn new(config: &ThemeConfig) -> Result<Self, ThemeError> {
+        let no_color = std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty());
+        Theme::build(config, no_color)
+    }
+
+    fn build(config: &ThemeConfig, no_color: bool) -> Result<Self, ThemeError> {
+        let mut theme = match (no_color, config.name) {
+            (true, _) => Theme::no_color(),
+            (false, ThemeName::Dark) => Theme::dark(),
+            (false, ThemeName::Light) => Theme::light(),
+            (false, ThemeName::HighContrast) => Theme::high_contrast(),
+        };
+
+        for (element, style) in &config.styles {
+            let target = match element {
+                Element::Popup => &mut theme.popup,
+                Element::Border => &mut theme.border,
+                Element::Selected => &mut theme.selected,
+                Element::Row => &mut theme.row,
+                Element::RowAlt => &mut theme.row_alt,
+                Element::Key => &mut theme.key,
+                Element::Success => &mut theme.success,
+                Element::Error => &mut theme.error,
+                Element::Changed => &mut theme.changed,
+                Element::Hint => &mut theme.hint,
+            };
+            let color = |color: &str| {
+                color
+                    .parse::<Color>()
+                    .map_err(|_| ThemeError::InvalidColor {
+                        element: format!("{element:?}"),
+                        color: color.to_string(),
+                    })
+            };
+            if let Some(fg) = &style.fg {
+                *target = target.fg(color(fg)?);
+            }
+            if let Some(bg) = &style.bg {
+                *target = target.bg(color(bg)?);
+            }
+            match style.bold {
+                Some(true) => *target = target.add_modifier(Modifier::BOLD),
+                Some(false) => *target = target.remove_modifier(Modifier::BOLD),
+                None => {}
+            }
+        }
+
+        Ok(theme)
+    }
+
+    pub fn dark() -> Self {
+        Theme {
+            popup: Style::new()
+                .bg(tailwind::INDIGO.c900)
+                .fg(tailwind::INDIGO.c300),
+            border: Style::new().fg(tailwind::INDIGO.c300),
+            selected: Style::new()
+                .fg(tailwind::INDIGO.c950)
+                .bg(tailwind::INDIGO.c300),
+            row: Style::new().bg(tailwind::INDIGO.c900),
+            row_alt: Style::new().bg(tailwind::INDIGO.c950),
+            key: Style::new().fg(Color::Blue),
+            success: Style::new().fg(tailwind::GREEN.c400),
+            error: Style::new().fg(tailwind::RED.c500),
+            changed: Style::new()
+                .fg(tailwind::AMBER.c300)
+                .add_modifier(Modifier::BOLD),
+            hint: Style::new()
+                .fg(tailwind::RED.c200)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+
+    pub fn light() -> Self {
+        Theme {
+            popup: Style::new()
+                .bg(tailwind::INDIGO.c100)
+                .fg(tailwind::INDIGO.c900),
+            border: Style::new().fg(tailwind::INDIGO.c700),
+            selected: Style::new().fg(Color::White).bg(tailwind::INDIGO.c600),
+            row: Style::new()
+                .bg(tailwind::SLATE.c100)
+                .fg(tailwind::SLATE.c900),
+            row_alt: Style::new()
+                .bg(tailwind::SLATE.c50)
+                .fg(tailwind::SLATE.c900),
+            key: Style::new().fg(tailwind::BLUE.c700),
+            success: Style::new().fg(tailwind::GREEN.c700),
+            error: Style::new().fg(tailwind::RED.c700),
+            changed: Style::new()
+                .fg(tailwind::AMBER.c700)
+                .add_modifier(Modifier::BOLD),
+            hint: Style::new()
+                .fg(tailwind::RED.c700)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Theme {
+            popup: Style::new().bg(Color::Black).fg(Color::White),
+            border: Style::new().fg(Color::White),
+            selected: Style::new()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            row: Style::new().bg(Color::Black).fg(Color::White),
+            row_alt: Style::new().bg(Color::Black).fg(Color::White),
+            key: Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            success: Style::new().fg(Color::LightGreen),
+            error: Style::new()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD),
+            changed: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            hint: Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// Only modifiers, see <https://no-color.org>
+    pub fn no_color() -> Self {
+        Theme {
+            popup: Style::new(),
+            border: Style::new(),
+            selected: Style::new().add_modifier(Modifier::REVERSED),
+            row: Style::new(),
+            row_alt: Style::new(),
+            key: Style::new().add_modifier(Modifier::BOLD),
+            success: Style::new(),
+            error: Style::new().add_modifier(Modifier::BOLD),
+            changed: Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            hint: Style::new().add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// Alternating row backgrounds
+    pub fn alternate(&self, i: usize) -> Style {
+        match i % 2 {
+            0 => self.row,
+            _ => self.row_alt,
+        }
+    }
+
+    // naive implementation of a filler based on colour
+    pub fn fill(&self, i: usize) -> Style {
+        self.alternate(i + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier};
+
+    use crate::theme::{Theme, ThemeConfig};
+
+    #[test]
+    fn test_theme_overrides() {
+        let config: ThemeConfig = toml::from_str(
+            r#"
+            name = 'light'
+
+            [styles.selected]
+            fg = 'black'
+            bg = '#ffd75f'
+            bold = true
+            "#,
+        )
+        .unwrap();
+        let theme = Theme::build(&config, false).unwrap();
+        assert_eq!(theme.selected.fg, Some(Color::Black));
+        assert_eq!(theme.selected.bg, Some(Color::Rgb(0xff, 0xd7, 0x5f)));
+        assert!(theme.selected.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(theme.border, Theme::light().border);
+    }
+
+    #[test]
+    fn test_theme_no_color() {
+        let config: ThemeConfig = toml::from_str("name = 'dark'").unwrap();
+        let theme = Theme::build(&config, true).unwrap();
+        assert_eq!(theme, Theme::no_color());
+        assert_eq!(theme.row.bg, None);
+    }
+
+    #[test]
+    fn test_theme_invalid() {
+        let config: ThemeConfig = toml::from_str("[styles.border]\nfg = 'not-a-colour'").unwrap();
+        assert!(Theme::build(&config, false).is_err());
+        assert!(toml::from_str::<ThemeConfig>("name = 'sepia'").is_err());
+    }
+}
@@ -0,0 +1,794 @@
+use std::sync::mpsc::Sender;
+
+use chrono::{Datelike, Days, NaiveDate};
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::Style;
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, BorderType, Cell, Clear, Paragraph, Row, Table, Wrap};
+use ratatui::Frame;
+
+use crate::api::{self, Api, Hours};
+use crate::hours;
+use crate::keys::{Action, Context, Keymap};
+use crate::model::{ActiveView, Message};
+use crate::picker::{self, PickerEvent, PickerItem, ProjectPicker};
+use crate::store::{DayEntry, Filter, Project, Store};
+use crate::task::{self, Task};
+use crate::theme::Theme;
+use crate::ui::centered_rect;
+
+const DAYS: usize = 7;
+
+/// Result of the last submission of a cell
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CellStatus {
+    #[default]
+    Unchanged,
+    Submitted,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimesheetCell {
+    pub original: f32,
+    pub value: f32,
+    pub status: CellStatus,
+}
+
+impl TimesheetCell {
+    pub fn changed(&self) -> bool {
+        self.value != self.original
+    }
+}
+
+/// Hours for one project, Monday to Sunday
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimesheetRow {
+    pub project: Project,
+    pub cells: [TimesheetCell; DAYS],
+}
+
+impl TimesheetRow {
+    fn new(project: Project) -> Self {
+        TimesheetRow {
+            project,
+            cells: Default::default(),
+        }
+    }
+
+    pub fn total(&self) -> f32 {
+        self.cells.iter().map(|c| c.value).sum()
+    }
+}
+
+#[derive(Debug)]
+pub struct TimesheetModel {
+    api: Api,
+    store: Store,
+    sender: Sender<Message>,
+    holidays: Vec<NaiveDate>,
+    keymap: Keymap,
+    theme: Theme,
+    /// Monday of the week being edited
+    pub week: NaiveDate,
+    pub rows: Vec<TimesheetRow>,
+    row: usize,
+    column: usize,
+    input: Option<String>,
+    picker: ProjectPicker,
+    adding: bool,
+    pub message: Option<String>,
+    /// Changed cells are being posted in the background, editing waits until they are done
+    pub submitting: bool,
+    /// Cells accepted and failed by the submission in progress
+    results: (usize, usize),
+    /// The date in the user's time zone, on the clock of the model
+    pub today: NaiveDate,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TimesheetMessage {
+    /// Opens the ISO week containing the date, with a row for the given project
+    Open {
+        date: NaiveDate,
+        project: Option<Project>,
+    },
+    /// Opens the ISO week containing the date with the hours of the week before proposed
+    CopyPreviousWeek(NaiveDate),
+    /// A cell was posted, or failed with the error
+    Posted {
+        project_id: String,
+        date: NaiveDate,
+        hours: f32,
+        error: Option<String>,
+    },
+    /// Every changed cell was posted, so the overviews are out of date
+    Submitted,
+}
+
+/// Monday of the ISO week containing the date
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(u64::from(date.weekday().num_days_from_monday()))
+}
+
+/// Builds one row per project from the entries of the week starting at `week`
+pub fn rows_from_entries(entries: &[DayEntry], week: NaiveDate) -> Vec<TimesheetRow> {
+    let mut rows: Vec<TimesheetRow> = vec![];
+    for entry in entries {
+        let Ok(day) = usize::try_from((entry.date - week).num_days()) else {
+            continue;
+        };
+        if day >= DAYS {
+            continue;
+        }
+
+        let i = match rows
+            .iter()
+            .position(|r| r.project.project_id == entry.project_id)
+        {
+            Some(i) => i,
+            None => {
+                rows.push(TimesheetRow::new(Project {
+                    project_id: entry.project_id.clone(),
+                    project_name: entry.project_name.clone(),
+                }));
+                rows.len() - 1
+            }
+        };
+        rows[i].cells[day].original = entry.hours;
+        rows[i].cells[day].value = entry.hours;
+    }
+    rows
+}
+
+/// Proposes the entries of the previous week, shifted by seven days, for every empty
+/// cell of the week starting at `week`. Holidays are skipped. Returns the number of
+/// proposed cells.
+pub fn propose_previous_week(
+    rows: &mut Vec<TimesheetRow>,
+    previous: &[DayEntry],
+    week: NaiveDate,
+    holidays: &[NaiveDate],
+) -> usize {
+    let mut proposed = 0;
+    for row in rows_from_entries(previous, week - Days::new(DAYS as u64)) {
+        let i = match rows
+            .iter()
+            .position(|r| r.project.project_id == row.project.project_id)
+        {
+            Some(i) => i,
+            None => {
+                rows.push(TimesheetRow::new(row.project.clone()));
+                rows.len() - 1
+            }
+        };
+
+        for (day, cell) in row.cells.iter().enumerate() {
+            let date = week + Days::new(day as u64);
+            let target = &mut rows[i].cells[day];
+            if cell.value == 0.0 || target.value != 0.0 || holidays.contains(&date) {
+                continue;
+            }
+            target.value = cell.value;
+            proposed += 1;
+        }
+    }
+    proposed
+}
+
+impl TimesheetModel {
+    pub fn new(
+        api: Api,
+        store: Store,
+        sender: Sender<Message>,
+        holidays: Vec<NaiveDate>,
+        keymap: Keymap,
+        theme: Theme,
+        today: NaiveDate,
+    ) -> Self {
+        TimesheetModel {
+            api,
+            store,
+            sender,
+            holidays,
+            keymap,
+            theme,
+            week: week_start(today),
+            rows: vec![],
+            row: 0,
+            column: 0,
+            input: None,
+            picker: ProjectPicker::default(),
+            adding: false,
+            message: None,
+            submitting: false,
+            results: (0, 0),
+            today,
+        }
+    }
+
+    /// Loads the week containing `date` from the store, discarding any changes
+    pub fn load(&mut self, date: NaiveDate, project: Option<Project>) -> Result<()> {
+        self.week = week_start(date);
+        let entries = self
+            .store
+            .get_entries(self.week, self.week + Days::new(DAYS as u64 - 1))?;
+        self.rows = rows_from_entries(&entries, self.week);
+        if let Some(project) = project {
+            self.add_row(project);
+        }
+
+        self.row = 0;
+        self.column = 0;
+        self.input = None;
+        self.adding = false;
+        self.message = None;
+        Ok(())
+    }
+
+    /// Fills the empty cells of the current week with the hours of the previous week,
+    /// leaving them as unsubmitted changes to review
+    fn copy_previous_week(&mut self) -> Result<()> {
+        let previous = self.week - Days::new(DAYS as u64);
+        let entries = self.store.get_entries(previous, self.week - Days::new(1))?;
+        let proposed = propose_previous_week(&mut self.rows, &entries, self.week, &self.holidays);
+        self.message = Some(format!(
+            "Copied {proposed} cells from week {}, review and submit <s>",
+            previous.iso_week().week()
+        ));
+        Ok(())
+    }
+
+    fn add_row(&mut self, project: Project) {
+        self.row = match self
+            .rows
+            .iter()
+            .position(|r| r.project.project_id == project.project_id)
+        {
+            Some(i) => i,
+            None => {
+                self.rows.push(TimesheetRow::new(project));
+                self.rows.len() - 1
+            }
+        };
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.rows.iter().flat_map(|r| &r.cells).any(|c| c.changed())
+    }
+
+    fn selected_cell(&mut self) -> Option<&mut TimesheetCell> {
+        self.rows
+            .get_mut(self.row)
+            .map(|r| &mut r.cells[self.column])
+    }
+
+    fn open_picker(&mut self) -> Result<()> {
+        let today = self.today;
+        let usage = self.store.project_usage(today.month(), today.year())?;
+        self.picker
+            .set_items(usage.into_iter().map(PickerItem::from).collect(), today);
+        self.adding = true;
+        Ok(())
+    }
+
+    /// Moves to another week unless there are changes which have not been submitted
+    fn change_week(&mut self, date: NaiveDate) -> Result<()> {
+        if self.has_changes() {
+            self.message = Some("Submit the changes before changing week".to_string());
+            return Ok(());
+        }
+        let projects: Vec<Project> = self.rows.iter().map(|r| r.project.clone()).collect();
+        self.load(date, None)?;
+        // keep the rows so the same projects can be filled in week after week
+        for project in projects {
+            self.add_row(project);
+        }
+        self.row = 0;
+        Ok(())
+    }
+
+    fn commit_input(&mut self) {
+        let Some(input) = self.input.take() else {
+            return;
+        };
+        match input.parse::<f32>() {
+            Ok(value) if (0.0..=24.0).contains(&value) => {
+                if let Some(cell) = self.selected_cell() {
+                    cell.value = value;
+                    cell.status = CellStatus::Unchanged;
+                }
+                self.message = None;
+            }
+            _ => self.message = Some(format!("Valid input 0h...24h (given: {input})")),
+        }
+    }
+
+    /// Posts every changed cell in the background, one after the other. The outcome of each
+    /// cell is sent as [`TimesheetMessage::Posted`], followed by [`TimesheetMessage::Submitted`].
+    pub fn submit(&mut self) {
+        let cells: Vec<(String, NaiveDate, f32)> = self
+            .rows
+            .iter()
+            .flat_map(|row| {
+                row.cells
+                    .iter()
+                    .zip(0..)
+                    .filter(|(cell, _)| cell.changed())
+                    .map(|(cell, day)| {
+                        let date = self.week + Days::new(day);
+                        (row.project.project_id.clone(), date, cell.value)
+                    })
+            })
+            .collect();
+        if cells.is_empty() {
+            self.message = Some("Nothing to submit".to_string());
+            return;
+        }
+
+        self.submitting = true;
+        self.results = (0, 0);
+        self.message = Some(format!("Submitting {} cells…", cells.len()));
+        let (api, store, sender) = (self.api.clone(), self.store.clone(), self.sender.clone());
+        task::spawn(Task::Submit, self.sender.clone(), move || {
+            for (project_id, date, hours) in cells {
+                let result = hours::submit(
+                    &api,
+                    &store,
+                    &Hours {
+                        project: &project_id,
+                        date,
+                        hours,
+                    },
+                    "timesheet",
+                );
+                let error = result.err().map(|err| {
+                    log::error!("{:?}", err);
+                    api::describe(&err)
+                });
+                let posted = TimesheetMessage::Posted {
+                    project_id,
+                    date,
+                    hours,
+                    error,
+                };
+                if sender.send(Message::Timesheet(posted)).is_err() {
+                    return Ok(());
+                }
+            }
+            let _ = sender.send(Message::Timesheet(TimesheetMessage::Submitted));
+            Ok(())
+        });
+    }
+
+    /// Records the outcome of a cell, unless it was changed again in the meantime
+    fn posted(&mut self, project_id: &str, date: NaiveDate, hours: f32, error: Option<String>) {
+        match error {
+            Some(_) => self.results.1 += 1,
+            None => self.results.0 += 1,
+        }
+        let day = usize::try_from((date - self.week).num_days()).unwrap_or(DAYS);
+        let Some(cell) = self
+            .rows
+            .iter_mut()
+            .find(|r| r.project.project_id == project_id)
+            .and_then(|r| r.cells.get_mut(day))
+            .filter(|cell| cell.value == hours)
+        else {
+            return;
+        };
+        match error {
+            // the cell stays changed, so submitting again retries it
+            Some(err) => cell.status = CellStatus::Failed(err),
+            None => {
+                cell.original = cell.value;
+                cell.status = CellStatus::Submitted;
+            }
+        }
+    }
+
+    /// Ends the submission in progress, returning the number of cells that were accepted
+    pub fn finish_submit(&mut self) -> usize {
+        self.submitting = false;
+        let (submitted, failed) = std::mem::take(&mut self.results);
+        self.message = Some(match (submitted, failed) {
+            (0, 0) => "Nothing to submit".to_string(),
+            (s, 0) => format!("Submitted {s} cells"),
+            (s, f) => format!("Submitted {s} cells, {f} failed"),
+        });
+        submitted
+    }
+
+    fn move_to(&mut self, row: usize, column: usize) {
+        self.commit_input();
+        self.row = row.min(self.rows.len().saturating_sub(1));
+        self.column = column.min(DAYS - 1);
+    }
+}
+
+pub fn handle_key(key: KeyEvent, model: &mut TimesheetModel) -> Result<Option<Message>> {
+    if model.submitting {
+        let close = model.keymap.action(Context::Timesheet, &key) == Some(Action::Close);
+        return Ok(close.then_some(Message::Home));
+    }
+
+    if model.adding {
+        match model.picker.handle_key(key, &model.keymap) {
+            Some(PickerEvent::Selected(Filter::Project(project))) => {
+                model.add_row(project);
+                model.adding = false;
+            }
+            Some(PickerEvent::Cancelled) => model.adding = false,
+            _ => {}
+        }
+        return Ok(None);
+    }
+
+    if let Some(input) = &mut model.input {
+        match key.code {
+            KeyCode::Char(c) if c.is_ascii_digit() || (c == '.' && !input.contains('.')) => {
+                input.push(c);
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => model.input = None,
+            KeyCode::Enter | KeyCode::Tab => model.commit_input(),
+            _ => {}
+        }
+        return Ok(None);
+    }
+
+    if let KeyCode::Char(c) = key.code {
+        if c.is_ascii_digit() && !model.rows.is_empty() {
+            model.input = Some(c.to_string());
+            return Ok(None);
+        }
+    }
+
+    match model.keymap.action(Context::Timesheet, &key) {
+        Some(action) => run_action(model, action),
+        None => Ok(None),
+    }
+}
+
+/// Runs an action of the timesheet keys, also when picked in the command palette
+pub fn run_action(model: &mut TimesheetModel, action: Action) -> Result<Option<Message>> {
+    let (row, column) = (model.row, model.column);
+    match action {
+        Action::Close => return Ok(Some(Message::Home)),
+        Action::Left => model.move_to(row, column.saturating_sub(1)),
+        Action::Right => model.move_to(row, column + 1),
+        Action::Up => model.move_to(row.saturating_sub(1), column),
+        Action::Down => model.move_to(row + 1, column),
+        Action::Clear => {
+            if let Some(cell) = model.selected_cell() {
+                cell.value = 0.0;
+            }
+        }
+        Action::AddProject => model.open_picker()?,
+        Action::CopyLastWeek => model.copy_previous_week()?,
+        Action::PreviousWeek => model.change_week(model.week - Days::new(DAYS as u64))?,
+        Action::NextWeek => model.change_week(model.week + Days::new(DAYS as u64))?,
+        Action::Submit => model.submit(),
+        _ => {}
+    }
+    Ok(None)
+}
+
+pub fn update(model: &mut TimesheetModel, msg: TimesheetMessage) -> Result<Option<Message>> {
+    match msg {
+        TimesheetMessage::Open { date, project } => {
+            model.load(date, project)?;
+            Ok(Some(Message::View(ActiveView::Timesheet)))
+        }
+        TimesheetMessage::CopyPreviousWeek(date) => {
+            model.load(date, None)?;
+            model.copy_previous_week()?;
+            Ok(Some(Message::View(ActiveView::Timesheet)))
+        }
+        TimesheetMessage::Posted {
+            project_id,
+            date,
+            hours,
+            error,
+        } => {
+            model.posted(&project_id, date, hours, error);
+            Ok(None)
+        }
+        TimesheetMessage::Submitted => Ok(None),
+    }
+}
+
+fn format_hours(hours: f32) -> String {
+    if hours == 0.0 {
+        "-".to_string()
+    } else {
+        format!("{hours:.1}")
+    }
+}
+
+pub fn render(f: &mut Frame, model: &mut TimesheetModel, area: Rect) {
+    let theme = model.theme;
+    f.render_widget(Clear, area);
+
+    let sunday = model.week + Days::new(DAYS as u64 - 1);
+    let block = Block::bordered()
+        .border_type(BorderType::Plain)
+        .style(theme.border)
+        .title(format!(
+            "Week {} ({} - {})",
+            model.week.iso_week().week(),
+            model.week.format("%d.%m"),
+            sunday.format("%d.%m.%Y")
+        ));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [table_area, help_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(inner);
+
+    let header = std::iter::once("Project".to_string())
+        .chain((0..DAYS).map(|d| {
+            (model.week + Days::new(d as u64))
+                .format("%a %d")
+                .to_string()
+        }))
+        .chain(std::iter::once("Total".to_string()))
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(theme.alternate(1))
+        .bottom_margin(1);
+
+    let rows = model.rows.iter().enumerate().map(|(r, row)| {
+        let cells = row.cells.iter().enumerate().map(|(c, cell)| {
+            let selected = r == model.row && c == model.column;
+            let text = match (&model.input, selected) {
+                (Some(input), true) => format!("{input}_"),
+                _ => format_hours(cell.value),
+            };
+            let style = match (&cell.status, cell.changed()) {
+                _ if selected => theme.selected,
+                (_, true) => theme.changed,
+                (CellStatus::Submitted, _) => theme.success,
+                (CellStatus::Failed(_), _) => theme.error,
+                _ => Style::default(),
+            };
+            Cell::from(text).style(style)
+        });
+        std::iter::once(Cell::from(row.project.project_name.clone()))
+            .chain(cells)
+            .chain(std::iter::once(Cell::from(format_hours(row.total()))))
+            .collect::<Row>()
+            .style(theme.alternate(r))
+    });
+
+    let day_totals = (0..DAYS).map(|d| {
+        let total: f32 = model.rows.iter().map(|r| r.cells[d].value).sum();
+        Cell::from(format_hours(total))
+    });
+    let week_total: f32 = model.rows.iter().map(TimesheetRow::total).sum();
+    let footer = std::iter::once(Cell::from("Total"))
+        .chain(day_totals)
+        .chain(std::iter::once(Cell::from(format_hours(week_total))))
+        .collect::<Row>()
+        .style(theme.alternate(1))
+        .top_margin(1);
+
+    let widths = std::iter::once(Constraint::Fill(1))
+        .chain(std::iter::repeat_n(Constraint::Length(7), DAYS + 1))
+        .collect::<Vec<Constraint>>();
+    let t = Table::new(rows, widths)
+        .header(header)
+        .footer(footer)
+        .style(theme.fill(model.rows.len()));
+    f.render_widget(t, table_area);
+
+    let failure = model
+        .rows
+        .get(model.row)
+        .and_then(|r| match &r.cells[model.column].status {
+            CellStatus::Failed(e) => Some(e.clone()),
+            _ => None,
+        });
+    let help = vec![
+        Line::from(
+            failure
+                .or_else(|| model.message.clone())
+                .unwrap_or_default(),
+        ),
+        Line::from(format!(
+            "Edit {}   {}",
+            Context::Timesheet.input_label().unwrap_or_default(),
+            model.keymap.hints(
+                Context::Timesheet,
+                &[
+                    Action::Clear,
+                    Action::AddProject,
+                    Action::CopyLastWeek,
+                    Action::PreviousWeek,
+                    Action::NextWeek,
+                    Action::Submit,
+                    Action::Close,
+                ]
+            )
+        ))
+        .style(theme.selected),
+    ];
+    f.render_widget(
+        Paragraph::new(Text::from(help))
+            .wrap(Wrap { trim: true })
+            .centered(),
+        help_area,
+    );
+
+    if model.adding {
+        let popup_area = centered_rect(40, 40, area);
+        f.render_widget(Clear, popup_area);
+        picker::render(
+            f,
+            &mut model.picker,
+            &theme,
+            popup_area,
+            "Add project",
+            &format!(
+                "Add {}   Back {}",
+                model.keymap.label(Context::Picker, Action::Select),
+                model.keymap.label(Context::Picker, Action::Close)
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use crossterm::event::KeyCode;
+
+    use crate::harness::{catalogue_store, Harness};
+    use crate::model::Message;
+    use crate::store::{DayEntry, Project};
+    use crate::timesheet::{
+        propose_previous_week, rows_from_entries, week_start, CellStatus, TimesheetMessage,
+    };
+
+    const PROJECT_A: &str = "mBED9hi2XFs51XzeH0hD";
+
+    fn entry(date: NaiveDate, project: &str, hours: f32) -> DayEntry {
+        DayEntry {
+            id: 0,
+            date,
+            hours,
+            project_name: project.to_string(),
+            project_id: project.to_lowercase(),
+        }
+    }
+
+    #[test]
+    fn test_week_start() {
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 22).unwrap();
+        assert_eq!(week_start(monday), monday);
+        let sunday = NaiveDate::from_ymd_opt(2024, 4, 28).unwrap();
+        assert_eq!(week_start(sunday), monday);
+    }
+
+    #[test]
+    fn test_rows_from_entries() {
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 22).unwrap();
+        let entries = vec![
+            entry(monday, "Alpha", 7.5),
+            entry(monday.succ_opt().unwrap(), "Alpha", 4.0),
+            entry(monday.succ_opt().unwrap(), "Beta", 3.5),
+            entry(NaiveDate::from_ymd_opt(2024, 4, 29).unwrap(), "Beta", 1.0),
+        ];
+        let rows = rows_from_entries(&entries, monday);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].project.project_name, "Alpha");
+        assert_eq!(rows[0].total(), 11.5);
+        assert_eq!(rows[1].cells[1].value, 3.5);
+        assert_eq!(rows[1].total(), 3.5, "entries outside the week are skipped");
+        assert!(rows.iter().flat_map(|r| &r.cells).all(|c| !c.changed()));
+    }
+
+    #[test]
+    fn test_propose_previous_week() {
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 22).unwrap();
+        let previous_monday = NaiveDate::from_ymd_opt(2024, 4, 15).unwrap();
+        let previous = vec![
+            entry(previous_monday, "Alpha", 7.5),
+            entry(previous_monday.succ_opt().unwrap(), "Alpha", 7.5),
+            entry(previous_monday.succ_opt().unwrap(), "Beta", 2.0),
+        ];
+        let mut rows = rows_from_entries(&[entry(monday, "Alpha", 4.0)], monday);
+
+        let holiday = monday.succ_opt().unwrap();
+        let proposed = propose_previous_week(&mut rows, &previous, monday, &[holiday]);
+
+        assert_eq!(proposed, 0, "logged hours are kept and holidays skipped");
+        assert_eq!(rows[0].cells[0].value, 4.0);
+        assert_eq!(rows.len(), 2, "projects from last week are added");
+
+        let proposed = propose_previous_week(&mut rows, &previous, monday, &[]);
+        assert_eq!(proposed, 2);
+        assert_eq!(rows[0].cells[1].value, 7.5);
+        assert!(rows[0].cells[1].changed());
+        assert_eq!(rows[1].cells[1].value, 2.0);
+    }
+
+    /// The timesheet of the last week of April 2024 with 8h on Monday and 4h on Tuesday
+    /// for project A, not submitted yet
+    fn filled_timesheet() -> Harness {
+        let mut harness = Harness::with_store(catalogue_store());
+        harness.send(Message::Timesheet(TimesheetMessage::Open {
+            date: harness.model.today(),
+            project: Some(Project {
+                project_id: PROJECT_A.to_string(),
+                project_name: "Project A".to_string(),
+            }),
+        }));
+        harness
+            .type_str("8")
+            .key(KeyCode::Enter)
+            .key(KeyCode::Right)
+            .type_str("4")
+            .key(KeyCode::Enter);
+        assert!(harness.model.timesheet_model.has_changes());
+        harness
+    }
+
+    fn submitted(msg: &Message) -> bool {
+        matches!(msg, Message::Timesheet(TimesheetMessage::Submitted))
+    }
+
+    #[test]
+    fn test_submit_cells() {
+        let mut harness = filled_timesheet();
+        harness.key(KeyCode::Char('s'));
+        assert!(harness.model.timesheet_model.submitting);
+        harness.wait_for(submitted);
+
+        let timesheet = &harness.model.timesheet_model;
+        assert!(!timesheet.submitting);
+        assert!(!timesheet.has_changes());
+        assert_eq!(timesheet.rows[0].cells[0].status, CellStatus::Submitted);
+        assert_eq!(timesheet.rows[0].cells[1].status, CellStatus::Submitted);
+        assert_eq!(timesheet.message.as_deref(), Some("Submitted 2 cells"));
+        assert_eq!(harness.api.requests("POST").len(), 2);
+        assert!(harness.model.overview[3].hours >= 12.0, "stored locally");
+    }
+
+    #[test]
+    fn test_submit_cells_retry_failed() {
+        let mut harness = filled_timesheet();
+        harness.api.fail_body("2024-04-30");
+        harness.key(KeyCode::Char('s'));
+        harness.wait_for(submitted);
+
+        let timesheet = &harness.model.timesheet_model;
+        assert_eq!(timesheet.rows[0].cells[0].status, CellStatus::Submitted);
+        assert!(
+            matches!(&timesheet.rows[0].cells[1].status, CellStatus::Failed(err) if err.contains("400")),
+            "{:?}",
+            timesheet.rows[0].cells[1].status
+        );
+        assert_eq!(
+            timesheet.message.as_deref(),
+            Some("Submitted 1 cells, 1 failed")
+        );
+        assert!(timesheet.has_changes(), "the failed cell is kept");
+
+        harness.api.recover();
+        harness.key(KeyCode::Char('s'));
+        harness.wait_for(submitted);
+
+        let timesheet = &harness.model.timesheet_model;
+        assert_eq!(timesheet.rows[0].cells[1].status, CellStatus::Submitted);
+        assert!(!timesheet.has_changes());
+        let posts = harness.api.requests("POST");
+        assert_eq!(posts.len(), 3, "only the failed cell is posted again");
+        assert!(posts[2].body.contains("2024-04-30"), "{}", posts[2].body);
+    }
+}
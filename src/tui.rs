@@ -1,21 +1,25 @@
+use log::error;
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
+        event::{DisableMouseCapture, EnableMouseCapture},
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
         ExecutableCommand,
     },
     Terminal,
 };
-use std::{io::stdout, panic};
+use std::{io::stdout, panic, thread};
 
 pub fn init_terminal() -> color_eyre::Result<Terminal<impl Backend>> {
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableMouseCapture)?;
     let terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
     Ok(terminal)
 }
 
 pub fn restore_terminal() -> color_eyre::Result<()> {
+    stdout().execute(DisableMouseCapture)?;
     stdout().execute(LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
@@ -24,6 +28,12 @@ pub fn restore_terminal() -> color_eyre::Result<()> {
 pub fn install_panic_hook() {
     let original_hook = panic::take_hook();
     panic::set_hook(Box::new(move |panic_info| {
+        // background tasks report their panics to the event loop, which keeps running
+        if thread::current().name() != Some("main") {
+            error!("{panic_info}");
+            return;
+        }
+        stdout().execute(DisableMouseCapture).unwrap();
         stdout().execute(LeaveAlternateScreen).unwrap();
         disable_raw_mode().unwrap();
         original_hook(panic_info);
@@ -1,53 +1,80 @@
 use crate::{
-    hours,
+    history, hours,
+    keys::{Action, Context},
     model::{ActiveView, Model},
-    project,
+    mouse::TableArea,
+    palette, project,
+    sync::{self, MonthStatus},
+    timesheet,
 };
 use chrono::Datelike;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{
-        palette::tailwind::{self},
-        Style, Stylize,
-    },
-    text::Text,
+    layout::{Constraint, Direction, Flex, Layout, Rect},
+    text::{Line, Text},
     widgets::{
         Block, BorderType, Borders, Cell, Clear, HighlightSpacing, Padding, Paragraph, Row, Table,
     },
     Frame,
 };
 
-pub const POPUP_STYLE: Style = Style::new()
-    .bg(tailwind::INDIGO.c900)
-    .fg(tailwind::INDIGO.c300);
-pub const BORDER_COLOR: Style = Style::new().fg(tailwind::INDIGO.c300);
-pub const SELECTED_COLOR: Style = Style::new()
-    .fg(tailwind::INDIGO.c950)
-    .bg(tailwind::INDIGO.c300);
-
-pub fn alternate_color(i: usize) -> Style {
-    match i % 2 {
-        0 => Style::default().bg(tailwind::INDIGO.c900),
-        _ => Style::default().bg(tailwind::INDIGO.c950),
-    }
+/// Smallest terminal the views fit in
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 12;
+/// Below this width panes are stacked, month names are shortened and the footer only shows notices
+const NARROW_WIDTH: u16 = 80;
+/// From this width the yearly overview and the month are shown side by side
+const WIDE_WIDTH: u16 = 120;
+/// Popups are never smaller than this, unless the view is
+const MIN_POPUP_WIDTH: u16 = 36;
+const MIN_POPUP_HEIGHT: u16 = 12;
+
+/// Layout chosen for the size of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Breakpoint {
+    /// Too small to show anything but a notice
+    Tiny,
+    Narrow,
+    Medium,
+    Wide,
 }
 
-// naive implementation of a filler based on colour
-pub fn fill_color(i: usize) -> Style {
-    match i % 2 {
-        0 => Style::default().bg(tailwind::INDIGO.c950),
-        _ => Style::default().bg(tailwind::INDIGO.c900),
+impl Breakpoint {
+    pub fn of(area: Rect) -> Self {
+        match area.width {
+            _ if area.width < MIN_WIDTH || area.height < MIN_HEIGHT => Breakpoint::Tiny,
+            w if w < NARROW_WIDTH => Breakpoint::Narrow,
+            w if w < WIDE_WIDTH => Breakpoint::Medium,
+            _ => Breakpoint::Wide,
+        }
     }
 }
 
 /// Renders the user interface widgets.
 pub fn view(model: &mut Model, frame: &mut Frame) {
+    let breakpoint = Breakpoint::of(frame.area());
+    if breakpoint == Breakpoint::Tiny {
+        render_too_small(frame);
+        return;
+    }
+
+    let footer = match (model.notice(), breakpoint) {
+        (Some(notice), _) => Some(format!("| {notice} |")),
+        (None, Breakpoint::Narrow) => None,
+        (None, _) => Some(format!(
+            "| release: {}-{} | config: {} | endpoint: {} |",
+            model.config.version,
+            model.config.commit,
+            model.config.config_location,
+            model.config.api.endpoint,
+        )),
+    };
+
     let main_layout = Layout::new(
         Direction::Vertical,
         [
             Constraint::Length(1),
             Constraint::Min(0),
-            Constraint::Length(1),
+            Constraint::Length(u16::from(footer.is_some())),
         ],
     )
     .split(frame.area());
@@ -56,64 +83,112 @@ pub fn view(model: &mut Model, frame: &mut Frame) {
         Block::new()
             .borders(Borders::TOP)
             .title(format!(
-                "| timet | {} |",
-                if model.active_project.is_some() {
-                    &model.active_project.as_ref().unwrap().project_name
-                } else {
-                    "NA"
+                "| timet |{} {} |{}",
+                match &model.config.profile {
+                    Some(profile) => format!(" {profile} |"),
+                    None => String::new(),
+                },
+                match &model.active_filter {
+                    Some(filter) => filter.name(),
+                    None => "NA",
+                },
+                match model.sync_status() {
+                    Some(status) => format!(" {status} |"),
+                    None => String::new(),
                 }
             ))
             .title_alignment(ratatui::layout::Alignment::Center),
         main_layout[0],
     );
-    frame.render_widget(
-        Block::new()
-            .borders(Borders::TOP)
-            .title(format!(
-                "| release: {}-{} | config: {} | endpoint: {} |",
-                model.config.version,
-                model.config.commit,
-                model.config.config_location,
-                model.config.api.endpoint,
-            ))
-            .title_alignment(ratatui::layout::Alignment::Center),
-        main_layout[2],
-    );
-
-    let inner_layout = Layout::new(
-        Direction::Horizontal,
-        [Constraint::Percentage(80), Constraint::Percentage(20)],
-    )
-    .split(main_layout[1]);
+    if let Some(footer) = footer {
+        frame.render_widget(
+            Block::new()
+                .borders(Borders::TOP)
+                .title(footer)
+                .title_alignment(ratatui::layout::Alignment::Center),
+            main_layout[2],
+        );
+    }
 
-    render_help(frame, inner_layout[1]);
+    let banner = model
+        .active_error_msg
+        .as_ref()
+        .filter(|_| model.active_view != ActiveView::Loading);
+    let [banner_area, content] = Layout::vertical([
+        Constraint::Length(u16::from(banner.is_some())),
+        Constraint::Min(0),
+    ])
+    .areas(main_layout[1]);
+    if let Some(error) = banner {
+        // the hint stays visible however long the error is
+        let hint = format!(
+            " Dismiss {} ",
+            model.keymap.label(Context::Global, Action::Home)
+        );
+        let [error_area, hint_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(u16::try_from(hint.chars().count()).unwrap_or(u16::MAX)),
+        ])
+        .areas(banner_area);
+        let style = model.theme.popup.patch(model.theme.error);
+        frame.render_widget(Paragraph::new(format!(" {error}")).style(style), error_area);
+        frame.render_widget(Paragraph::new(hint).style(style), hint_area);
+    }
 
-    let inner_overview = Layout::new(
-        Direction::Vertical,
-        [Constraint::Percentage(50), Constraint::Percentage(50)],
-    )
-    .split(inner_layout[0]);
+    let inner_overview = match breakpoint {
+        Breakpoint::Wide => Layout::new(
+            Direction::Horizontal,
+            [Constraint::Percentage(40), Constraint::Percentage(60)],
+        ),
+        _ => Layout::new(
+            Direction::Vertical,
+            [Constraint::Percentage(50), Constraint::Percentage(50)],
+        ),
+    }
+    .split(content);
+    // on narrow terminals the year takes the whole height when there is nothing to suggest
+    let home_area = match breakpoint {
+        Breakpoint::Narrow if model.suggestions.is_empty() => content,
+        _ => inner_overview[0],
+    };
 
     match model.active_view {
         ActiveView::Loading => {
             render_loading(frame, model);
         }
         ActiveView::Home => {
-            render_home(frame, model, inner_overview[0]);
+            render_home(frame, model, home_area, breakpoint);
+            render_suggestions(frame, model, inner_overview[1]);
         }
         ActiveView::Month => {
-            render_home(frame, model, inner_overview[0]);
-            render_active_month(frame, model, inner_overview[1]);
+            render_home(frame, model, inner_overview[0], breakpoint);
+            render_active_month(frame, model, inner_overview[1], breakpoint);
         }
         ActiveView::Hours => {
-            project::render(frame, &mut model.register_model, inner_layout[0]);
+            project::render(frame, &mut model.register_model, content);
+        }
+        ActiveView::LogHours => hours::render(frame, &mut model.add_hours_model, content),
+        ActiveView::Timesheet => {
+            timesheet::render(frame, &mut model.timesheet_model, content);
         }
-        ActiveView::LogHours => hours::render(frame, &mut model.add_hours_model, inner_layout[0]),
+        ActiveView::History => history::render(frame, &mut model.history_model, content),
+        ActiveView::Palette => {
+            render_home(frame, model, home_area, breakpoint);
+            render_suggestions(frame, model, inner_overview[1]);
+            palette::render(frame, &mut model.palette_model, content);
+        }
+    }
+
+    if model.show_help {
+        render_help(frame, model, content);
     }
 }
 
-fn render_help(f: &mut Frame, area: Rect) {
-    let key_style = Style::default().blue();
+/// Lists the keys of the active view from the keymap, on top of the view
+fn render_help(f: &mut Frame, model: &Model, area: Rect) {
+    let theme = model.theme;
+    let area = centered_rect(50, 60, area);
+    f.render_widget(Clear, area);
 
     let header = ["Key", "Operation"]
         .into_iter()
@@ -121,36 +196,30 @@ fn render_help(f: &mut Frame, area: Rect) {
         .collect::<Row>()
         .height(2);
 
-    let key_map = vec![
-        ("H", "Home screen"),
-        ("o", "Overview"),
-        ("r", "Refresh database"),
-        ("l", "Log hours"),
-        ("p", "Active project"),
-        ("k", "Up"),
-        ("j", "Down"),
-        ("Enter", "Select"),
-        ("q", "Quit application"),
-    ];
-
-    let keys = key_map
-        .iter()
-        .map(|c| {
+    let keys = model
+        .keymap
+        .help(model.active_view.contexts())
+        .into_iter()
+        .map(|(keys, description)| {
             Row::new(vec![
-                Cell::from(Text::from(c.0)).style(key_style),
-                Cell::from(Text::from(c.1)).style(key_style),
+                Cell::from(Text::from(keys)).style(theme.key),
+                Cell::from(Text::from(description)).style(theme.key),
             ])
             .height(1)
         })
         .collect::<Vec<Row>>();
 
-    let t = Table::new(keys, [Constraint::Min(2), Constraint::Length(15 + 1)])
+    let t = Table::new(keys, [Constraint::Length(15 + 1), Constraint::Fill(1)])
         .header(header)
         .block(
             Block::bordered()
                 .border_type(BorderType::Plain)
-                .style(BORDER_COLOR)
-                .title("Help"),
+                .style(theme.popup)
+                .title("Help")
+                .title_bottom(format!(
+                    "Close <any key>   All actions {}",
+                    model.keymap.label(Context::Global, Action::Palette)
+                )),
         )
         .highlight_spacing(HighlightSpacing::Always);
 
@@ -158,104 +227,150 @@ fn render_help(f: &mut Frame, area: Rect) {
 }
 
 fn render_loading(f: &mut Frame, model: &mut Model) {
-    let area = f.area();
-    let block = Block::bordered().padding(Padding::new(5, 10, 1, 2));
-    let paragraph = Paragraph::new(format!(
-        "Rebuilding database ({}/{} months)",
-        model.update_month,
-        model.now.month()
-    ))
-    .centered()
-    .style(POPUP_STYLE)
-    .block(block);
-
-    let area = centered_rect(40, 10, area);
+    let theme = model.theme;
+    let area = centered_rect(40, 60, f.area());
     f.render_widget(Clear, area);
-    f.render_widget(paragraph, area);
+
+    let months = model.refreshing.as_ref().map_or(&[][..], |r| &r.months[..]);
+    let done = months
+        .iter()
+        .filter(|m| **m != MonthStatus::Pending)
+        .count();
+    let rows = months.iter().zip(1..).map(|(status, month)| {
+        let (text, style) = match status {
+            MonthStatus::Pending => ("pending".to_string(), theme.hint),
+            MonthStatus::Synced => ("ok".to_string(), theme.success),
+            MonthStatus::Failed(err) => (format!("failed: {err}"), theme.error),
+        };
+        Row::new(vec![
+            Cell::from(sync::month_name(month)),
+            Cell::from(text).style(style),
+        ])
+    });
+    let t = Table::new(rows, [Constraint::Length(10), Constraint::Fill(1)])
+        .block(
+            Block::bordered()
+                .padding(Padding::horizontal(1))
+                .title(format!(
+                    "Rebuilding database ({done}/{} months)",
+                    months.len()
+                ))
+                .title_bottom(format!(
+                    "Cancel {}",
+                    model.keymap.label(Context::Loading, Action::Close)
+                )),
+        )
+        .style(theme.popup);
+    f.render_widget(t, area);
 
     if let Some(error) = &model.active_error_msg {
         let block = Block::bordered();
         let paragraph_error = Paragraph::new(error.to_string())
             .centered()
-            .style(POPUP_STYLE.patch(Style::default().fg(tailwind::RED.c500)))
+            .style(theme.popup.patch(theme.error))
             .block(block);
         f.render_widget(Clear, area);
         f.render_widget(paragraph_error, area);
     };
 }
 
-/// Aligns the popup to the center of the view
-pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::vertical([
-        Constraint::Percentage((100 - percent_y) / 2),
-        Constraint::Percentage(percent_y),
-        Constraint::Percentage((100 - percent_y) / 2),
-    ])
-    .split(r);
+/// Shown instead of the views when the terminal is smaller than they need
+fn render_too_small(f: &mut Frame) {
+    let area = f.area();
+    let text = vec![
+        Line::from("Terminal too small"),
+        Line::from(format!("{}x{}", area.width, area.height)),
+        Line::from(format!("needs {MIN_WIDTH}x{MIN_HEIGHT}")),
+    ];
+    let [middle] = Layout::vertical([Constraint::Length(3)])
+        .flex(Flex::Center)
+        .areas(area);
+    f.render_widget(Paragraph::new(text).centered(), middle);
+}
 
-    Layout::horizontal([
-        Constraint::Percentage((100 - percent_x) / 2),
-        Constraint::Percentage(percent_x),
-        Constraint::Percentage((100 - percent_x) / 2),
-    ])
-    .split(popup_layout[1])[1]
+/// Aligns the popup to the center of the view, keeping it large enough for its content
+/// on small terminals
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let scale = |length: u16, percent: u16, min: u16| {
+        let scaled = u32::from(length) * u32::from(percent) / 100;
+        u16::try_from(scaled).unwrap_or(length).max(min).min(length)
+    };
+    let width = scale(r.width, percent_x, MIN_POPUP_WIDTH);
+    let height = scale(r.height, percent_y, MIN_POPUP_HEIGHT);
+    Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + (r.height - height) / 2,
+        width,
+        height,
+    }
 }
 
-fn render_home(f: &mut Frame, model: &mut Model, area: Rect) {
+fn render_home(f: &mut Frame, model: &mut Model, area: Rect, breakpoint: Breakpoint) {
+    let theme = model.theme;
     let header = ["Month", "Hours"]
         .into_iter()
         .map(ratatui::widgets::Cell::from)
         .collect::<Row>()
-        .style(alternate_color(1))
+        .style(theme.alternate(1))
         .bottom_margin(1)
         .height(1);
+    // single lines fit more months when the panes are stacked on narrow terminals
+    let row_height = match breakpoint {
+        Breakpoint::Narrow => 1,
+        _ => 2,
+    };
     let rows = model.overview.iter().enumerate().map(|(i, data)| {
-        vec![&data.month_name, &data.hours.to_string()]
+        let month_name = match breakpoint {
+            Breakpoint::Narrow => data.month_name.chars().take(3).collect(),
+            _ => data.month_name.clone(),
+        };
+        vec![month_name, data.hours.to_string()]
             .into_iter()
-            .map(|content| Cell::from(Text::from(content.to_string())))
+            .map(|content| Cell::from(Text::from(content)))
             .collect::<Row>()
-            .style(alternate_color(i))
-            .height(2)
+            .style(theme.alternate(i))
+            .height(row_height)
     });
     let bar = " █ ";
+    let block = Block::bordered()
+        .border_type(BorderType::Plain)
+        .title(format!("{}", model.today().year()));
+    model.year_area = TableArea::new(block.inner(area), 2, row_height);
     let t = Table::new(rows, [Constraint::Min(2), Constraint::Length(15 + 1)])
         .header(header)
-        .block(
-            Block::bordered()
-                .border_type(BorderType::Plain)
-                .title(format!("{}", model.now.year())),
-        )
-        .row_highlight_style(SELECTED_COLOR)
+        .block(block)
+        .row_highlight_style(theme.selected)
         .highlight_symbol(Text::from(vec![bar.into(), bar.into()]))
-        .style(fill_color(model.overview.len()))
+        .style(theme.fill(model.overview.len()))
         .highlight_spacing(HighlightSpacing::Always);
 
     f.render_stateful_widget(t, area, &mut model.table_state);
 }
 
-fn render_active_month(f: &mut Frame, model: &mut Model, area: Rect) {
-    let month = chrono::NaiveDate::from_ymd_opt(model.active_year, model.active_month, 1)
-        .unwrap()
-        .format("%B");
+fn render_suggestions(f: &mut Frame, model: &mut Model, area: Rect) {
+    if model.suggestions.is_empty() {
+        return;
+    }
+    let theme = model.theme;
 
     let header = ["Date", "Project", "Hours"]
         .into_iter()
         .map(ratatui::widgets::Cell::from)
         .collect::<Row>()
-        .style(alternate_color(1))
+        .style(theme.alternate(1))
         .bottom_margin(1)
         .height(1);
 
-    let rows = model.overview_month.iter().enumerate().map(|(i, data)| {
+    let rows = model.suggestions.iter().enumerate().map(|(i, data)| {
         vec![
-            data.date.format("%D").to_string(),
+            data.date.format("%a %D").to_string(),
             data.project_name.to_string(),
             format!("{:.1}", data.hours),
         ]
         .into_iter()
-        .map(|content| Cell::from(Text::from(content.to_string())))
+        .map(|content| Cell::from(Text::from(content)))
         .collect::<Row>()
-        .style(alternate_color(i))
+        .style(theme.alternate(i))
         .height(1)
     });
 
@@ -264,7 +379,7 @@ fn render_active_month(f: &mut Frame, model: &mut Model, area: Rect) {
         [
             Constraint::Fill(1),
             Constraint::Fill(2),
-            Constraint::Fill(3),
+            Constraint::Fill(1),
         ],
     )
     .header(header)
@@ -272,11 +387,215 @@ fn render_active_month(f: &mut Frame, model: &mut Model, area: Rect) {
         Block::bordered()
             .border_type(BorderType::Plain)
             .borders(Borders::TOP | Borders::BOTTOM)
-            .title(format!("{}", month)),
+            .title("Suggested recurring hours")
+            .title_bottom(model.keymap.hints(
+                Context::Home,
+                &[
+                    Action::AcceptSuggestion,
+                    Action::AcceptAllSuggestions,
+                    Action::DismissSuggestion,
+                ],
+            )),
     )
-    // .style(fill_color(model.overview_month.len()))
-    .style(fill_color(1))
+    .style(theme.fill(1))
     .highlight_spacing(HighlightSpacing::Always);
 
     f.render_widget(t, area);
 }
+
+fn render_active_month(f: &mut Frame, model: &mut Model, area: Rect, breakpoint: Breakpoint) {
+    let theme = model.theme;
+    let month = chrono::NaiveDate::from_ymd_opt(model.active_year, model.active_month, 1)
+        .unwrap()
+        .format("%B");
+    let block = Block::bordered()
+        .border_type(BorderType::Plain)
+        .borders(Borders::TOP | Borders::BOTTOM)
+        .title(format!("{}", month));
+    model.month_area = TableArea::new(block.inner(area), 2, 1);
+    // the git activity is left out on narrow terminals
+    let columns = match breakpoint {
+        Breakpoint::Narrow => 3,
+        _ => 4,
+    };
+
+    let header = ["Date", "Project", "Hours", "Activity"]
+        .into_iter()
+        .take(columns)
+        .map(ratatui::widgets::Cell::from)
+        .collect::<Row>()
+        .style(theme.alternate(1))
+        .bottom_margin(1)
+        .height(1);
+
+    let rows = model.overview_month.iter().enumerate().map(|(i, data)| {
+        vec![
+            data.date.format("%D").to_string(),
+            data.project_name.to_string(),
+            format!("{:.1}", data.hours),
+            model
+                .activity_for(data)
+                .map(|a| a.window())
+                .unwrap_or_default(),
+        ]
+        .into_iter()
+        .take(columns)
+        .map(|content| Cell::from(Text::from(content)))
+        .collect::<Row>()
+        .style(theme.alternate(i))
+        .height(1)
+    });
+
+    let widths = [
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+    ];
+    let t = Table::new(rows, &widths[..columns])
+        .header(header)
+        .block(block)
+        .row_highlight_style(theme.selected)
+        // .style(theme.fill(model.overview_month.len()))
+        .style(theme.fill(1))
+        .highlight_spacing(HighlightSpacing::Always);
+
+    f.render_stateful_widget(t, area, &mut model.month_table_state);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crossterm::event::KeyCode;
+    use ratatui::layout::Rect;
+
+    use crate::harness::Harness;
+    use crate::model::{ActiveView, Message};
+    use crate::store::Store;
+    use crate::ui::Breakpoint;
+
+    /// The application without any hours
+    fn empty() -> Harness {
+        Harness::with_store(Store::memory().unwrap())
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        assert_eq!(Breakpoint::of(Rect::new(0, 0, 39, 40)), Breakpoint::Tiny);
+        assert_eq!(Breakpoint::of(Rect::new(0, 0, 200, 11)), Breakpoint::Tiny);
+        assert_eq!(Breakpoint::of(Rect::new(0, 0, 40, 12)), Breakpoint::Narrow);
+        assert_eq!(Breakpoint::of(Rect::new(0, 0, 80, 24)), Breakpoint::Medium);
+        assert_eq!(Breakpoint::of(Rect::new(0, 0, 120, 24)), Breakpoint::Wide);
+    }
+
+    #[test]
+    fn test_view_tiny() {
+        assert_eq!(
+            empty().render(30, 8),
+            [
+                "                              ",
+                "                              ",
+                "                              ",
+                "      Terminal too small      ",
+                "             30x8             ",
+                "          needs 40x12         ",
+                "                              ",
+                "                              ",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_view_narrow() {
+        assert_eq!(
+            empty().render(44, 14),
+            [
+                "───────────────| timet | NA |───────────────",
+                "┌2024──────────────────────────────────────┐",
+                "│   Month                  Hours           │",
+                "│                                          │",
+                "│ █ Jan                    0               │",
+                "│   Feb                    0               │",
+                "│   Mar                    0               │",
+                "│   Apr                    0               │",
+                "│   May                    0               │",
+                "│   Jun                    0               │",
+                "│   Jul                    0               │",
+                "│   Aug                    0               │",
+                "│   Sep                    0               │",
+                "└──────────────────────────────────────────┘",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_view_medium() {
+        let lines = empty().render(100, 30);
+        assert!(lines[4].contains("January"), "{}", lines[4]);
+        assert!(lines[29].contains("endpoint: <endpoint>"), "{}", lines[29]);
+    }
+
+    #[test]
+    fn test_view_wide() {
+        let mut harness = empty();
+        harness.model.set_active_month().unwrap();
+        harness.model.active_view = ActiveView::Month;
+        let lines = harness.render(140, 30);
+        assert!(
+            lines[1].starts_with("┌2024") && lines[1].contains("January"),
+            "panes side by side: {}",
+            lines[1]
+        );
+        assert!(lines[2].contains("Activity"), "{}", lines[2]);
+    }
+
+    #[test]
+    fn test_render_home() {
+        let mut harness = Harness::new();
+        harness.assert_snapshot("home", 100, 30);
+
+        harness.type_str("jjj").key(KeyCode::Enter);
+        assert_eq!(harness.model.active_view, ActiveView::Month);
+        harness.assert_snapshot("month", 100, 30);
+    }
+
+    #[test]
+    fn test_render_refresh() {
+        let mut harness = Harness::with_store(Store::memory().unwrap());
+        harness.api.delay(Duration::from_millis(50));
+        harness.send(Message::RefreshStarted);
+        harness.assert_snapshot("loading", 100, 30);
+
+        // statuses as they arrive, before the workers report
+        let generation = harness.model.refreshing.as_ref().unwrap().generation;
+        harness.model.refresh_month(generation, 1, None);
+        harness.model.refresh_month(
+            generation,
+            3,
+            Some("Timet failed with status 502".to_string()),
+        );
+        harness.assert_snapshot("loading_progress", 100, 30);
+
+        harness.wait_for(|m| matches!(m, Message::RefreshCompleted(_) | Message::TaskFailed(_)));
+        assert_eq!(harness.model.active_view, ActiveView::Home);
+        assert_eq!(
+            harness.api.requests("GET").len(),
+            5,
+            "projects and four months"
+        );
+        harness.assert_snapshot("home_refreshed", 100, 30);
+    }
+
+    #[test]
+    fn test_render_overlays() {
+        let mut harness = Harness::new();
+        harness.type_str("?");
+        harness.assert_snapshot("help", 100, 30);
+
+        harness.type_str("x:");
+        harness.type_str("timesheet");
+        assert_eq!(harness.model.active_view, ActiveView::Palette);
+        harness.assert_snapshot("palette", 100, 30);
+    }
+}
@@ -0,0 +1,666 @@
+use std::sync::mpsc::Sender;
+
+use color_eyre::{eyre::eyre, Result};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, MouseEvent};
+use log::error;
+
+use crate::api::Api;
+use crate::config::Config;
+use crate::history::{self, HistoryMessage};
+use crate::hours;
+use crate::keys::{Action, Context};
+use crate::model::{ActiveView, Message, Model, RunningState};
+use crate::mouse::Gesture;
+use crate::palette::{self, PaletteMessage};
+use crate::project;
+use crate::store::{self, Filter, Store};
+use crate::sync::{Refresh, Refreshing};
+use crate::task::{Task, TaskFailure};
+use crate::timesheet::{self, TimesheetMessage};
+
+/// Builds the model for the given profile, with its own database and API key
+pub fn load_model(sender: Sender<Message>, profile: Option<&str>) -> Result<Model> {
+    let config = Config::new(profile)?;
+
+    let store = Store::new(&config)?;
+    let remote_api = Api::new(&config);
+    Model::new(sender, remote_api, store, config)
+}
+
+/// Maps a terminal event to a message
+pub fn handle_event(event: Event, model: &mut Model) -> Result<Option<Message>> {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => handle_key(key, model),
+        Event::Mouse(mouse) => handle_mouse(mouse, model),
+        _ => Ok(None),
+    }
+}
+
+/// Maps a key press to a message, handling selection and the help directly
+pub fn handle_key(key: KeyEvent, model: &mut Model) -> Result<Option<Message>> {
+    // Any key closes the help, and the help opens in every view unless the key is typed
+    if model.show_help {
+        model.show_help = false;
+        return Ok(None);
+    }
+    let typing = matches!(
+        model.active_view,
+        ActiveView::Hours | ActiveView::LogHours | ActiveView::Palette
+    ) && matches!(key.code, KeyCode::Char(_));
+    if !typing && model.keymap.action(Context::Global, &key) == Some(Action::Help) {
+        model.show_help = true;
+        return Ok(None);
+    }
+
+    // Views taking text input receive keys before the global keys
+    match model.active_view {
+        ActiveView::Hours => return project::handle_key(key, &mut model.register_model),
+        ActiveView::LogHours => return hours::handle_key(key, &mut model.add_hours_model),
+        ActiveView::Timesheet => return timesheet::handle_key(key, &mut model.timesheet_model),
+        ActiveView::History => return history::handle_key(key, &mut model.history_model),
+        ActiveView::Palette => return palette::handle_key(key, &mut model.palette_model),
+        _ => {}
+    }
+
+    let global = model.keymap.action(Context::Global, &key);
+    let action = match model.active_view {
+        ActiveView::Home => global.or_else(|| model.keymap.action(Context::Home, &key)),
+        ActiveView::Loading => match model.keymap.action(Context::Loading, &key) {
+            Some(Action::Close) => return Ok(Some(Message::RefreshCancelled)),
+            _ => global,
+        },
+        _ => global,
+    };
+
+    match action {
+        Some(action) => run_action(model, action),
+        None => Ok(None),
+    }
+}
+
+/// Maps clicks and scrolling in the tables and popups to the same messages as the keys
+pub fn handle_mouse(event: MouseEvent, model: &mut Model) -> Result<Option<Message>> {
+    let Some(gesture) = model.clicks.gesture(event) else {
+        return Ok(None);
+    };
+    let click = matches!(gesture, Gesture::Click | Gesture::DoubleClick);
+    if model.show_help {
+        model.show_help = !click;
+        return Ok(None);
+    }
+
+    match model.active_view {
+        ActiveView::Hours => {
+            return Ok(project::handle_mouse(
+                event,
+                gesture,
+                &mut model.register_model,
+            ))
+        }
+        ActiveView::LogHours => {
+            return Ok(hours::handle_mouse(
+                event,
+                gesture,
+                &mut model.add_hours_model,
+            ))
+        }
+        ActiveView::Month if model.month_area.contains(event) => {
+            return Ok(handle_month_mouse(event, gesture, model));
+        }
+        ActiveView::Home | ActiveView::Month => {}
+        _ => return Ok(None),
+    }
+
+    match gesture {
+        Gesture::ScrollDown => run_action(model, Action::Down),
+        Gesture::ScrollUp => run_action(model, Action::Up),
+        Gesture::Click | Gesture::DoubleClick => {
+            let Some(row) = model
+                .year_area
+                .row(event, model.table_state.offset())
+                .filter(|row| *row < model.overview.len())
+            else {
+                return Ok(None);
+            };
+            model.table_state.select(Some(row));
+            model.set_active_month()?;
+            let drill_in =
+                gesture == Gesture::DoubleClick || model.active_view == ActiveView::Month;
+            Ok(drill_in.then_some(Message::DetailMonth))
+        }
+    }
+}
+
+/// Selects entries of the active month, double-clicking opens the entry in the timesheet
+fn handle_month_mouse(event: MouseEvent, gesture: Gesture, model: &mut Model) -> Option<Message> {
+    match gesture {
+        Gesture::ScrollDown => model.month_table_state.select_next(),
+        Gesture::ScrollUp => model.month_table_state.select_previous(),
+        Gesture::Click | Gesture::DoubleClick => {
+            let row = model
+                .month_area
+                .row(event, model.month_table_state.offset())
+                .filter(|row| *row < model.overview_month.len())?;
+            model.month_table_state.select(Some(row));
+            if gesture == Gesture::DoubleClick {
+                let entry = &model.overview_month[row];
+                return Some(Message::Timesheet(TimesheetMessage::Open {
+                    date: entry.date,
+                    project: Some(store::Project {
+                        project_id: entry.project_id.clone(),
+                        project_name: entry.project_name.clone(),
+                    }),
+                }));
+            }
+        }
+    }
+    None
+}
+
+/// Runs an action picked in the command palette from the home screen.
+/// Actions of other views open the view first, then run in it.
+fn run_command(model: &mut Model, context: Context, action: Action) -> Result<Option<Message>> {
+    model.active_view = ActiveView::Home;
+    let open = match context {
+        Context::Global | Context::Home => return run_action(model, action),
+        Context::Loading => return Ok(Some(Message::RefreshCancelled)),
+        Context::Picker => return run_action(model, Action::Palette),
+        Context::Project => Action::ActiveProject,
+        Context::Hours => Action::LogHours,
+        Context::Timesheet => Action::Timesheet,
+        Context::History => Action::History,
+    };
+    let mut current = run_action(model, open)?;
+    while let Some(msg) = current {
+        current = update(model, msg)?;
+    }
+
+    match (context, model.active_view) {
+        (Context::Project, ActiveView::Hours) => Ok(project::run_action(action)),
+        (Context::Hours, ActiveView::LogHours) => {
+            hours::run_action(&mut model.add_hours_model, action)
+        }
+        (Context::Timesheet, ActiveView::Timesheet) => {
+            timesheet::run_action(&mut model.timesheet_model, action)
+        }
+        (Context::History, ActiveView::History) => {
+            history::run_action(&mut model.history_model, action)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Maps an action of the global or home keys to a message
+fn run_action(model: &mut Model, action: Action) -> Result<Option<Message>> {
+    match action {
+        Action::Home => Ok(Some(Message::Home)),
+        Action::Help => {
+            model.show_help = true;
+            Ok(None)
+        }
+        Action::Palette => Ok(Some(Message::Palette(PaletteMessage::Open))),
+        Action::Quit => Ok(Some(Message::Quit)),
+        Action::Undo => Ok(Some(Message::Undo)),
+        Action::LogHours => Ok(Some(Message::AddHours(hours::HoursMessage::Open(
+            model
+                .active_filter
+                .as_ref()
+                .and_then(|f| f.project())
+                .map(|p| p.project_id.clone()),
+        )))),
+        Action::ActiveProject => Ok(Some(Message::Hours(project::ProjectMessage::Open))),
+        Action::Refresh => Ok(Some(Message::RefreshStarted)),
+        Action::History => Ok(Some(Message::History(HistoryMessage::Open))),
+        Action::SwitchProfile => Ok(Some(Message::SwitchProfile)),
+        Action::AcceptSuggestion => Ok(Some(Message::AcceptSuggestions(false))),
+        Action::AcceptAllSuggestions => Ok(Some(Message::AcceptSuggestions(true))),
+        Action::DismissSuggestion => Ok(Some(Message::DismissSuggestion)),
+        Action::CopyLastWeek => Ok(Some(Message::Timesheet(
+            TimesheetMessage::CopyPreviousWeek(model.today()),
+        ))),
+        Action::Timesheet => Ok(Some(Message::Timesheet(TimesheetMessage::Open {
+            date: model.today(),
+            project: model
+                .active_filter
+                .as_ref()
+                .and_then(|f| f.project())
+                .cloned(),
+        }))),
+        Action::Down => {
+            model.next_row()?;
+            Ok(None)
+        }
+        Action::Up => {
+            model.previous_row()?;
+            Ok(None)
+        }
+        Action::Select => {
+            model.set_active_month()?;
+            Ok(Some(Message::DetailMonth))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Applies the message and every message following from it.
+/// Returns whether the view has to be drawn again.
+pub fn apply(model: &mut Model, msg: Message) -> Result<bool> {
+    if let Message::Tick(now) = msg {
+        return model.tick(now);
+    }
+    let mut current = Some(msg);
+    while let Some(msg) = current {
+        current = update(model, msg)?;
+    }
+    Ok(true)
+}
+
+/// Returns to the view the refresh was started from, unless the user has
+/// already moved on to another view while it was loading
+fn leave_loading(model: &Model) -> Option<Message> {
+    (model.active_view == ActiveView::Loading).then_some(Message::View(model.previous_view))
+}
+
+/// Shows the failure in the error banner, leaving the view of a failed refresh
+fn task_failed(model: &mut Model, failure: TaskFailure) -> Result<Option<Message>> {
+    match failure.task {
+        Task::Refresh(generation) => {
+            // a failure of a refresh which was cancelled or has ended no longer matters
+            if !model.is_refreshing(generation) {
+                return Ok(None);
+            }
+            let Some(refreshing) = model.finish_refresh(false) else {
+                return Ok(None);
+            };
+            // the months stored before the failure are kept
+            model.reload()?;
+            if refreshing.refresh == Refresh::Background {
+                model.notify(failure.to_string());
+                return Ok(None);
+            }
+            model.active_error_msg = Some(failure.to_string());
+            Ok(leave_loading(model))
+        }
+        // hours which were posted before the failure are stored
+        Task::Submit => {
+            model.submitting = false;
+            model.add_hours_model.submitting = false;
+            if model.timesheet_model.submitting {
+                model.timesheet_model.finish_submit();
+            }
+            model.active_error_msg = Some(failure.to_string());
+            model.reload()?;
+            Ok(None)
+        }
+        // without input the application can no longer be used, or even quit
+        Task::Input => Err(eyre!(failure.to_string())),
+    }
+}
+
+/// Applies the message to the model, returning the message to apply next, if any
+pub fn update(model: &mut Model, msg: Message) -> Result<Option<Message>> {
+    match msg {
+        Message::View(view) => {
+            model.active_view = view;
+            Ok(None)
+        }
+        Message::Home => {
+            model.active_error_msg = None;
+            Ok(Some(Message::View(ActiveView::Home)))
+        }
+        Message::DetailMonth => {
+            model.set_active_month()?;
+            Ok(Some(Message::View(ActiveView::Month)))
+        }
+        Message::RefreshStarted => {
+            if model.active_view != ActiveView::Loading {
+                model.previous_view = model.active_view;
+            }
+            model.start_refresh(Refresh::Manual);
+            Ok(Some(Message::View(ActiveView::Loading)))
+        }
+        Message::RefreshMonth(generation, month, error) => {
+            model.refresh_month(generation, month, error);
+            Ok(None)
+        }
+        Message::RefreshCompleted(generation) => {
+            if !model.is_refreshing(generation) {
+                return Ok(None);
+            }
+            let failures = model.refreshing.as_ref().and_then(Refreshing::failures);
+            let Some(refreshing) = model.finish_refresh(failures.is_none()) else {
+                return Ok(None);
+            };
+            model.reload()?;
+            if let Some(failures) = failures {
+                model.notify(failures);
+            }
+            match refreshing.refresh {
+                Refresh::Background => Ok(None),
+                Refresh::Manual => Ok(leave_loading(model)),
+            }
+        }
+        Message::RefreshCancelled => {
+            let Some(refreshing) = model.cancel_refresh() else {
+                return Ok(None);
+            };
+            model.notify(format!(
+                "Refresh cancelled, kept {} synced month(s)",
+                refreshing.synced()
+            ));
+            model.reload()?;
+            model.active_error_msg = None;
+            Ok(leave_loading(model))
+        }
+        Message::TaskFailed(failure) => task_failed(model, failure),
+        Message::Activity(year, month, activity) => {
+            model.set_activity(year, month, activity)?;
+            Ok(None)
+        }
+        Message::Submitted(description) => {
+            let undo = model.keymap.label(Context::Global, Action::Undo);
+            model.notify(format!("{description} - undo {undo}"));
+            model.reload()?;
+            Ok(Some(Message::View(ActiveView::Home)))
+        }
+        Message::SwitchProfile => {
+            let next = model.config.next_profile();
+            let name = next.as_deref().unwrap_or("default").to_string();
+            match load_model(model.sender.clone(), next.as_deref()) {
+                Ok(switched) => {
+                    // the refresh syncs the store of the previous profile
+                    model.cancel_refresh();
+                    *model = switched;
+                    model.notify(format!("Switched to profile {name}"));
+                }
+                Err(err) => {
+                    error!("{err:?}");
+                    model.notify(format!("Profile {name}: {}", err.root_cause()));
+                }
+            }
+            Ok(None)
+        }
+        Message::Undo => {
+            model.undo();
+            Ok(None)
+        }
+        Message::Undone(undone) => {
+            model.undone(undone)?;
+            Ok(None)
+        }
+        Message::AcceptSuggestions(all) => {
+            model.accept_suggestions(all);
+            Ok(None)
+        }
+        Message::SuggestionsAccepted(accepted, error) => {
+            model.suggestions_accepted(accepted, error)?;
+            Ok(None)
+        }
+        Message::DismissSuggestion => {
+            model.dismiss_suggestion()?;
+            Ok(None)
+        }
+        Message::Hours(m) => project::update(&mut model.register_model, m),
+        Message::Input(event) => handle_event(event, model),
+        Message::Tick(now) => {
+            model.tick(now)?;
+            Ok(None)
+        }
+        Message::Quit => {
+            model.running_state = RunningState::Done;
+            Ok(None)
+        }
+        Message::ActiveFilter(filter) => {
+            match &filter {
+                Some(Filter::Project(p)) => model.store.insert_active_project(&p.project_id)?,
+                Some(Filter::Group { name, project_ids }) => {
+                    model.store.insert_active_group(name, project_ids)?;
+                    let unknown = model.store.unknown_projects(project_ids)?;
+                    if !unknown.is_empty() {
+                        model.notify(format!(
+                            "Group '{name}': {} of {} project(s) have no hours or catalogue entry",
+                            unknown.len(),
+                            project_ids.len()
+                        ));
+                    }
+                }
+                None => model.store.delete_active_filter()?,
+            }
+            model.active_filter = filter;
+            model.overview = model.store.get_yearly_overview(model.active_year)?;
+            Ok(Some(Message::View(ActiveView::Home)))
+        }
+        Message::AddHours(hmsg) => hours::update(&mut model.add_hours_model, hmsg),
+        Message::Timesheet(TimesheetMessage::Submitted) => {
+            if model.timesheet_model.finish_submit() > 0 {
+                let undo = model.keymap.label(Context::Global, Action::Undo);
+                model.notify(format!(
+                    "Timesheet submitted - undo {undo} restores the last cell"
+                ));
+                model.overview = model.store.get_yearly_overview(model.active_year)?;
+                model.set_suggestions()?;
+            }
+            Ok(None)
+        }
+        Message::Timesheet(tmsg) => timesheet::update(&mut model.timesheet_model, tmsg),
+        Message::History(hmsg) => history::update(&mut model.history_model, hmsg),
+        Message::Palette(pmsg) => palette::update(&mut model.palette_model, pmsg),
+        Message::Command(context, action) => run_command(model, context, action),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{Local, NaiveDate, TimeZone, Utc};
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+
+    use crate::config::Repository;
+    use crate::harness::Harness;
+    use crate::model::{ActiveView, Message, RunningState};
+    use crate::sync::Refresh;
+    use crate::task::{Task, TaskFailure};
+    use crate::update::{apply, update};
+
+    #[test]
+    fn test_tick() {
+        let local = |y, m, d, h, min, s| {
+            Local
+                .with_ymd_and_hms(y, m, d, h, min, s)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let mut harness = Harness::new();
+        let model = &mut harness.model;
+        apply(model, Message::Tick(local(2024, 4, 30, 12, 0, 0))).unwrap();
+        let later = local(2024, 4, 30, 23, 59, 0);
+        assert!(!apply(model, Message::Tick(later)).unwrap(), "same day");
+        assert_eq!(model.now, later);
+
+        let midnight = local(2024, 5, 1, 0, 0, 1);
+        assert!(apply(model, Message::Tick(midnight)).unwrap(), "next day");
+        let may_first = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        assert_eq!(model.today(), may_first);
+        assert_eq!(model.add_hours_model.today, may_first, "the views follow");
+        assert_eq!(model.timesheet_model.today, may_first);
+
+        let new_year = local(2025, 1, 1, 0, 0, 1);
+        assert!(apply(model, Message::Tick(new_year)).unwrap());
+        assert_eq!(model.active_year, 2025);
+        assert!(model.overview.iter().all(|m| m.hours == 0.0));
+    }
+
+    #[test]
+    fn test_palette_command() {
+        let mut harness = Harness::new();
+        harness
+            .key(KeyCode::Char(':'))
+            .type_str("timesheet next week");
+        harness.key(KeyCode::Enter);
+        assert_eq!(harness.model.active_view, ActiveView::Timesheet);
+        assert_eq!(
+            harness.model.timesheet_model.week,
+            NaiveDate::from_ymd_opt(2024, 5, 6).unwrap(),
+            "opens the timesheet, then shows the next week"
+        );
+
+        harness.key(KeyCode::Esc).key(KeyCode::Char(':'));
+        harness.key(KeyCode::F(1));
+        assert!(harness.model.show_help);
+        assert!(
+            harness
+                .render(100, 30)
+                .iter()
+                .any(|line| line.contains("Enter") && line.contains("Select")),
+            "the help lists the keys of the palette"
+        );
+    }
+
+    #[test]
+    fn test_scroll_past_december() {
+        let mut harness = Harness::new();
+        let scroll = MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            column: 10,
+            row: 10,
+            modifiers: KeyModifiers::NONE,
+        };
+        for _ in 0..15 {
+            apply(&mut harness.model, Message::Input(Event::Mouse(scroll))).unwrap();
+        }
+        assert_eq!(harness.model.table_state.selected(), Some(11));
+        assert_eq!(harness.model.active_month, 12, "stays on December");
+        harness.render(100, 30);
+    }
+
+    #[test]
+    fn test_input() {
+        let mut harness = Harness::new();
+        let quit = Event::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert!(apply(&mut harness.model, Message::Input(quit)).unwrap());
+        assert_eq!(harness.model.running_state, RunningState::Done);
+    }
+
+    #[test]
+    fn test_background_refresh() {
+        let mut harness = Harness::configured(|config| {
+            config.refresh_interval = Some(std::time::Duration::from_secs(15 * 60));
+        });
+        harness.send(Message::DetailMonth);
+        let start = harness.model.now;
+        let minutes = |m| start + chrono::Duration::minutes(m);
+
+        assert!(!apply(&mut harness.model, Message::Tick(minutes(10))).unwrap());
+        assert_eq!(harness.model.refresh_kind(), None);
+
+        assert!(apply(&mut harness.model, Message::Tick(minutes(15))).unwrap());
+        assert_eq!(harness.model.refresh_kind(), Some(Refresh::Background));
+        assert!(harness.render(100, 30)[0].contains("syncing"));
+
+        harness.wait_for(|m| matches!(m, Message::RefreshCompleted(_) | Message::TaskFailed(_)));
+        assert_eq!(
+            harness.model.active_view,
+            ActiveView::Month,
+            "stays in the view"
+        );
+        assert_eq!(harness.model.refresh_kind(), None);
+        assert_eq!(harness.model.last_synced, Some(minutes(15)));
+        assert!(harness.render(100, 30)[0].contains("| last synced just now |"));
+
+        assert!(apply(&mut harness.model, Message::Tick(minutes(20))).unwrap());
+        assert!(harness.render(100, 30)[0].contains("| last synced 5 min ago |"));
+        assert!(!apply(&mut harness.model, Message::Tick(minutes(20))).unwrap());
+
+        assert!(apply(&mut harness.model, Message::Tick(minutes(30))).unwrap());
+        assert_eq!(
+            harness.model.refresh_kind(),
+            Some(Refresh::Background),
+            "every interval"
+        );
+    }
+
+    #[test]
+    fn test_input_failure() {
+        let mut harness = Harness::new();
+        let failure = TaskFailure {
+            task: Task::Input,
+            error: "Reading terminal events: broken pipe".to_string(),
+            panicked: false,
+        };
+        let err = update(&mut harness.model, Message::TaskFailed(failure)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Input failed: Reading terminal events: broken pipe"
+        );
+    }
+
+    #[test]
+    fn test_activity_failure() {
+        let mut harness = Harness::configured(|config| {
+            config.git.repositories = vec![Repository {
+                path: "/nonexistent/timet-tui".to_string(),
+                project: "mBED9hi2XFs51XzeH0hD".to_string(),
+            }];
+        });
+        harness.model.table_state.select(Some(3));
+        harness.send(Message::DetailMonth);
+        assert_eq!(
+            harness.model.active_error_msg, None,
+            "git runs in the background"
+        );
+
+        harness.wait_for(|m| matches!(m, Message::Activity(..)));
+        let error = harness.model.active_error_msg.take().unwrap();
+        assert!(error.starts_with("Git activity: "), "{error}");
+        assert!(
+            !harness.model.overview_month.is_empty(),
+            "the month is kept"
+        );
+
+        harness.send(Message::DetailMonth);
+        harness.drain(Duration::from_millis(200));
+        assert_eq!(
+            harness.model.active_error_msg, None,
+            "the failure is not collected again"
+        );
+    }
+
+    #[test]
+    fn test_reload_month_activity() {
+        let mut harness = Harness::configured(|config| {
+            config.git.repositories = vec![Repository {
+                path: "/nonexistent/timet-tui".to_string(),
+                project: "mBED9hi2XFs51XzeH0hD".to_string(),
+            }];
+        });
+        harness.model.table_state.select(Some(3));
+        harness.send(Message::DetailMonth);
+        harness.wait_for(|m| matches!(m, Message::Activity(..)));
+        harness.model.active_error_msg = None;
+
+        harness.model.reload().unwrap();
+        harness.wait_for(|m| matches!(m, Message::Activity(..)));
+        assert!(
+            harness.model.active_error_msg.is_some(),
+            "the activity of the open month is collected again"
+        );
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut harness = Harness::new();
+        harness.send(Message::Undo);
+        assert!(harness.model.submitting, "undone in the background");
+        harness.send(Message::Undo);
+        assert_eq!(
+            harness.model.notice(),
+            Some("Still submitting, try again in a moment")
+        );
+
+        harness.wait_for(|m| matches!(m, Message::Undone(_)));
+        assert!(!harness.model.submitting);
+        assert_eq!(harness.model.notice(), Some("Nothing to undo"));
+        assert!(harness.api.requests("POST").is_empty());
+    }
+}